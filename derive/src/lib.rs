@@ -0,0 +1,545 @@
+//! `#[derive(Component)]` for super-shape-battle.
+//!
+//! ```ignore
+//! #[derive(Component, Debug)]
+//! #[component(storage = "ordered", name = "drawable", debug)]
+//! pub struct Drawable { .. }
+//! ```
+//!
+//! - `storage`: `"sparse_set"` (the default) or `"ordered"`
+//! - `name`: stable name used when saving and inspecting, defaults to the type
+//!   name
+//! - `debug`: include the component's `Debug` output when inspecting
+//! - `reflect`: expose the component's `Reflect` impl to tools
+//! - `immutable`: only the world may change the component, so it can't be
+//!   borrowed mutably through `World::get_mut`, `&mut T` queries or reflection.
+//!   Implied by `#[entity]` fields, which the world indexes
+//! - `on_despawn`: what happens to the component when an entity one of its
+//!   `#[entity]` fields points at is despawned: `"remove"` it (the default),
+//!   `"clear"` the reference, or `"report"` it
+//!
+//! Fields marked `#[entity]` hold an `EntityId`, `Option<EntityId>` or
+//! `Vec<EntityId>`. Only optional and list fields can be cleared, so clearing
+//! a plain `EntityId` removes the component instead.
+//!
+//! Every other component implements `Mutable`, which `World::get_mut` and
+//! `&mut T` queries require:
+//!
+//! ```
+//! # mod components {
+//! #   pub enum StorageKind { SparseSet, Ordered }
+//! #   pub trait Component {
+//! #     fn name() -> &'static str;
+//! #     fn storage_kind() -> StorageKind;
+//! #     fn as_any(&self) -> &dyn std::any::Any;
+//! #   }
+//! #   pub trait Mutable: Component {}
+//! # }
+//! # use super_shape_battle_derive::Component;
+//! #[derive(Component)]
+//! struct Transform;
+//!
+//! fn get_mut<T: components::Mutable>() {}
+//!
+//! fn main() {
+//!   get_mut::<Transform>();
+//! }
+//! ```
+//!
+//! so an immutable component like `Tag` can't be borrowed mutably:
+//!
+//! ```compile_fail
+//! # mod components {
+//! #   pub enum StorageKind { SparseSet, Ordered }
+//! #   pub trait Component {
+//! #     fn name() -> &'static str;
+//! #     fn storage_kind() -> StorageKind;
+//! #     fn as_any(&self) -> &dyn std::any::Any;
+//! #   }
+//! #   pub trait Mutable: Component {}
+//! # }
+//! # use super_shape_battle_derive::Component;
+//! #[derive(Component)]
+//! #[component(immutable)]
+//! struct Tag;
+//!
+//! fn get_mut<T: components::Mutable>() {}
+//!
+//! fn main() {
+//!   get_mut::<Tag>();
+//! }
+//! ```
+//!
+//! `#[derive(Reflect)]` gives generic field access to structs with named
+//! fields. Fields marked `#[reflect(skip)]` are left out.
+//! `#[derive(FromReflect)]` builds the struct back from those fields, filling
+//! skipped fields with `Default::default()`.
+
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, LitStr};
+
+#[proc_macro_derive(Component, attributes(component, entity))]
+pub fn derive_component(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+
+  expand(input)
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn derive_reflect(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+
+  expand_reflect(input)
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+#[proc_macro_derive(FromReflect, attributes(reflect))]
+pub fn derive_from_reflect(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+
+  expand_from_reflect(input)
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+struct Options {
+  storage: TokenStream,
+  name: String,
+  debug: bool,
+  reflect: bool,
+  immutable: bool,
+  on_despawn: Option<TokenStream>,
+}
+
+fn parse_options(input: &DeriveInput) -> syn::Result<Options> {
+  let mut options = Options {
+    storage: quote!(crate::components::StorageKind::SparseSet),
+    name: input.ident.to_string(),
+    debug: false,
+    reflect: false,
+    immutable: false,
+    on_despawn: None,
+  };
+
+  for attr in input
+    .attrs
+    .iter()
+    .filter(|a| a.path().is_ident("component"))
+  {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("storage") {
+        let storage: LitStr = meta.value()?.parse()?;
+        options.storage = match storage.value().as_str() {
+          "sparse_set" => quote!(crate::components::StorageKind::SparseSet),
+          "ordered" => quote!(crate::components::StorageKind::Ordered),
+          _ => {
+            return Err(syn::Error::new(
+              storage.span(),
+              "expected storage = \"sparse_set\" or \"ordered\"",
+            ))
+          }
+        };
+        Ok(())
+      } else if meta.path.is_ident("name") {
+        let name: LitStr = meta.value()?.parse()?;
+        options.name = name.value();
+        Ok(())
+      } else if meta.path.is_ident("debug") {
+        options.debug = true;
+        Ok(())
+      } else if meta.path.is_ident("reflect") {
+        options.reflect = true;
+        Ok(())
+      } else if meta.path.is_ident("immutable") {
+        options.immutable = true;
+        Ok(())
+      } else if meta.path.is_ident("on_despawn") {
+        let policy: LitStr = meta.value()?.parse()?;
+        options.on_despawn = Some(match policy.value().as_str() {
+          "remove" => quote!(crate::components::OnDespawn::Remove),
+          "clear" => quote!(crate::components::OnDespawn::Clear),
+          "report" => quote!(crate::components::OnDespawn::Report),
+          _ => {
+            return Err(syn::Error::new(
+              policy.span(),
+              "expected on_despawn = \"remove\", \"clear\" or \"report\"",
+            ))
+          }
+        });
+        Ok(())
+      } else {
+        Err(meta.error("unsupported component attribute"))
+      }
+    })?;
+  }
+
+  Ok(options)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+  let options = parse_options(&input)?;
+  let entities = entity_fields(&input)?;
+  let ident = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+  let Options {
+    storage,
+    name,
+    debug,
+    reflect,
+    immutable,
+    on_despawn,
+  } = options;
+  let immutable = immutable || !entities.is_empty();
+
+  let debug = if debug {
+    quote! {
+      fn debug(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+      }
+    }
+  } else {
+    quote!()
+  };
+
+  let reflect = match (reflect, immutable) {
+    (true, false) => quote! {
+      fn as_reflect(&self) -> Option<&dyn crate::reflect::Reflect> {
+        Some(self)
+      }
+
+      fn as_reflect_mut(&mut self) -> Option<&mut dyn crate::reflect::Reflect> {
+        Some(self)
+      }
+    },
+    (true, true) => quote! {
+      fn as_reflect(&self) -> Option<&dyn crate::reflect::Reflect> {
+        Some(self)
+      }
+    },
+    (false, _) => quote!(),
+  };
+
+  let mutable = if immutable {
+    quote!()
+  } else {
+    quote! {
+      impl #impl_generics crate::components::Mutable for #ident #ty_generics #where_clause {}
+    }
+  };
+
+  let entity_refs = match (entities.is_empty(), on_despawn) {
+    (true, None) => quote!(),
+    (true, Some(_)) => {
+      return Err(syn::Error::new_spanned(
+        ident,
+        "on_despawn needs at least one #[entity] field",
+      ))
+    }
+    (false, on_despawn) => {
+      let on_despawn = on_despawn.unwrap_or(quote!(crate::components::OnDespawn::Remove));
+
+      quote! {
+        fn on_despawn() -> Option<crate::components::OnDespawn> {
+          Some(#on_despawn)
+        }
+
+        fn entity_refs(&self) -> Vec<crate::entity::EntityId> {
+          let mut refs = vec![];
+          #(crate::components::EntityRef::collect(&self.#entities, &mut refs);)*
+          refs
+        }
+
+        fn clear_entity_ref(&mut self, entity: &crate::entity::EntityId) -> bool {
+          let mut cleared = true;
+          #(cleared &= crate::components::EntityRef::clear(&mut self.#entities, entity);)*
+          cleared
+        }
+      }
+    }
+  };
+
+  Ok(quote! {
+    impl #impl_generics crate::components::Component for #ident #ty_generics #where_clause {
+      fn name() -> &'static str {
+        #name
+      }
+
+      fn storage_kind() -> crate::components::StorageKind {
+        #storage
+      }
+
+      #debug
+
+      #reflect
+
+      #entity_refs
+
+      fn as_any(&self) -> &dyn ::std::any::Any {
+        self
+      }
+    }
+
+    #mutable
+  })
+}
+
+fn entity_fields(input: &DeriveInput) -> syn::Result<Vec<syn::Ident>> {
+  let fields = match &input.data {
+    Data::Struct(data) => &data.fields,
+    _ => return Ok(vec![]),
+  };
+
+  let mut entities = vec![];
+  for field in fields {
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("entity")) {
+      attr.meta.require_path_only()?;
+      match &field.ident {
+        Some(ident) => entities.push(ident.clone()),
+        None => {
+          return Err(syn::Error::new_spanned(
+            attr,
+            "#[entity] is only supported on named fields",
+          ))
+        }
+      }
+    }
+  }
+
+  Ok(entities)
+}
+
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+  let mut skip = false;
+
+  for attr in field.attrs.iter().filter(|a| a.path().is_ident("reflect")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("skip") {
+        skip = true;
+        Ok(())
+      } else {
+        Err(meta.error("unsupported reflect attribute"))
+      }
+    })?;
+  }
+
+  Ok(skip)
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<&Punctuated<Field, Comma>> {
+  match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => Ok(&fields.named),
+      _ => Err(syn::Error::new_spanned(
+        &input.ident,
+        "reflection is only supported for structs with named fields",
+      )),
+    },
+    _ => Err(syn::Error::new_spanned(
+      &input.ident,
+      "reflection is only supported for structs",
+    )),
+  }
+}
+
+fn expand_reflect(input: DeriveInput) -> syn::Result<TokenStream> {
+  let fields = named_fields(&input)?;
+
+  let mut idents = vec![];
+  for field in fields {
+    if !is_skipped(field)? {
+      idents.push(field.ident.clone().unwrap());
+    }
+  }
+  let names: Vec<String> = idents.iter().map(|i| i.to_string()).collect();
+
+  let ident = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics crate::reflect::Reflect for #ident #ty_generics #where_clause {
+      fn fields(&self) -> Vec<(&'static str, crate::reflect::Value)> {
+        vec![
+          #((#names, crate::reflect::FieldValue::to_value(&self.#idents)),)*
+        ]
+      }
+
+      fn set_field(
+        &mut self,
+        name: &str,
+        value: &crate::reflect::Value,
+      ) -> Result<(), crate::reflect::ReflectError> {
+        match name {
+          #(
+            #names => {
+              self.#idents = crate::reflect::FieldValue::from_value(value)
+                .ok_or_else(|| crate::reflect::ReflectError::InvalidValue(name.to_string()))?;
+              Ok(())
+            }
+          )*
+          _ => Err(crate::reflect::ReflectError::UnknownField(name.to_string())),
+        }
+      }
+    }
+  })
+}
+
+fn expand_from_reflect(input: DeriveInput) -> syn::Result<TokenStream> {
+  let mut assignments = vec![];
+
+  for field in named_fields(&input)? {
+    let ident = field.ident.clone().unwrap();
+    let name = ident.to_string();
+
+    assignments.push(if is_skipped(field)? {
+      quote!(#ident: Default::default())
+    } else {
+      quote! {
+        #ident: {
+          let value = fields
+            .get(#name)
+            .ok_or_else(|| crate::reflect::ReflectError::MissingField(#name.to_string()))?;
+          crate::reflect::FieldValue::from_value(value)
+            .ok_or_else(|| crate::reflect::ReflectError::InvalidValue(#name.to_string()))?
+        }
+      }
+    });
+  }
+
+  let ident = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics crate::reflect::FromReflect for #ident #ty_generics #where_clause {
+      fn from_fields(
+        fields: &crate::reflect::FieldMap,
+      ) -> Result<Self, crate::reflect::ReflectError> {
+        Ok(#ident {
+          #(#assignments,)*
+        })
+      }
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use syn::parse_quote;
+
+  #[test]
+  fn reads_component_attributes() {
+    let input: DeriveInput = parse_quote! {
+      #[component(storage = "ordered", name = "ship_tag", debug)]
+      struct Tag;
+    };
+
+    let options = parse_options(&input).unwrap();
+
+    assert_eq!(
+      options.storage.to_string(),
+      quote!(crate::components::StorageKind::Ordered).to_string()
+    );
+    assert_eq!(options.name, "ship_tag");
+    assert!(options.debug);
+  }
+
+  #[test]
+  fn leaves_immutable_components_read_only() {
+    let mutable = expand(parse_quote! {
+      #[component(reflect)]
+      struct Transform;
+    })
+    .unwrap()
+    .to_string();
+    let immutable = expand(parse_quote! {
+      #[component(reflect, immutable)]
+      struct Tag;
+    })
+    .unwrap()
+    .to_string();
+
+    assert!(mutable.contains("Mutable for Transform"));
+    assert!(mutable.contains("as_reflect_mut"));
+    assert!(!immutable.contains("Mutable"));
+    assert!(!immutable.contains("as_reflect_mut"));
+    assert!(immutable.contains("as_reflect"));
+
+    let referencing = expand(parse_quote! {
+      #[component(reflect)]
+      struct Parent {
+        #[entity]
+        entity: EntityId,
+      }
+    })
+    .unwrap()
+    .to_string();
+
+    assert!(!referencing.contains("Mutable"));
+    assert!(!referencing.contains("as_reflect_mut"));
+  }
+
+  #[test]
+  fn rejects_unknown_attributes() {
+    let input: DeriveInput = parse_quote! {
+      #[component(storage = "table")]
+      struct Tag;
+    };
+    assert!(parse_options(&input).is_err());
+
+    let input: DeriveInput = parse_quote! {
+      #[component(pooled)]
+      struct Tag;
+    };
+    assert!(parse_options(&input).is_err());
+  }
+
+  #[test]
+  fn declares_entity_references() {
+    let input: DeriveInput = parse_quote! {
+      #[component(on_despawn = "clear")]
+      struct Target {
+        #[entity]
+        entity: Option<EntityId>,
+        distance: f32,
+      }
+    };
+
+    let expanded = expand(input).unwrap().to_string();
+
+    assert!(expanded.contains("OnDespawn :: Clear"));
+    assert!(expanded.contains("self . entity"));
+    assert!(!expanded.contains("self . distance"));
+
+    let input: DeriveInput = parse_quote! {
+      #[component(on_despawn = "clear")]
+      struct Target {
+        distance: f32,
+      }
+    };
+    assert!(expand(input).is_err());
+  }
+
+  #[test]
+  fn reflects_fields_that_are_not_skipped() {
+    let input: DeriveInput = parse_quote! {
+      struct Expirable {
+        #[reflect(skip)]
+        created: Instant,
+        expiration: Duration,
+      }
+    };
+
+    let expanded = expand_reflect(input).unwrap().to_string();
+
+    assert!(expanded.contains("\"expiration\""));
+    assert!(!expanded.contains("\"created\""));
+  }
+}
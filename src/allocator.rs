@@ -0,0 +1,133 @@
+use crate::entity::EntityId;
+
+/// Hands out generational entity ids, recycling the slots of removed
+/// entities with a bumped generation so stale ids can be detected.
+pub struct EntityAllocator {
+  generations: Vec<u32>,
+  alive: Vec<bool>,
+  free: Vec<u32>,
+}
+
+impl EntityAllocator {
+  pub fn new() -> Self {
+    EntityAllocator {
+      generations: vec![],
+      alive: vec![],
+      free: vec![],
+    }
+  }
+
+  pub fn allocate(&mut self) -> EntityId {
+    if let Some(index) = self.free.pop() {
+      let slot = index as usize;
+      self.alive[slot] = true;
+      return EntityId::new(index, self.generations[slot]);
+    }
+
+    let index = self.generations.len() as u32;
+    self.generations.push(0);
+    self.alive.push(true);
+
+    EntityId::new(index, 0)
+  }
+
+  pub fn free(&mut self, entity: &EntityId) -> bool {
+    if !self.is_alive(entity) {
+      return false;
+    }
+
+    let slot = entity.index() as usize;
+    self.alive[slot] = false;
+    self.generations[slot] = self.generations[slot].wrapping_add(1);
+    self.free.push(entity.index());
+
+    true
+  }
+
+  pub fn is_alive(&self, entity: &EntityId) -> bool {
+    let slot = entity.index() as usize;
+
+    slot < self.generations.len()
+      && self.alive[slot]
+      && self.generations[slot] == entity.generation()
+  }
+
+  #[cfg(test)]
+  pub fn len(&self) -> usize {
+    self.generations.len() - self.free.len()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = EntityId> + '_ {
+    self
+      .generations
+      .iter()
+      .zip(self.alive.iter())
+      .enumerate()
+      .filter(|(_, (_, alive))| **alive)
+      .map(|(index, (generation, _))| EntityId::new(index as u32, *generation))
+  }
+}
+
+impl Default for EntityAllocator {
+  fn default() -> Self {
+    EntityAllocator::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn allocates_unique_ids() {
+    let mut allocator = EntityAllocator::new();
+
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+
+    assert_ne!(entity1, entity2);
+    assert!(allocator.is_alive(&entity1));
+    assert!(allocator.is_alive(&entity2));
+    assert_eq!(allocator.len(), 2);
+  }
+
+  #[test]
+  fn recycles_freed_slots_with_a_new_generation() {
+    let mut allocator = EntityAllocator::new();
+
+    let entity1 = allocator.allocate();
+    assert!(allocator.free(&entity1));
+    let entity2 = allocator.allocate();
+
+    assert_eq!(entity1.index(), entity2.index());
+    assert_ne!(entity1.generation(), entity2.generation());
+    assert!(!allocator.is_alive(&entity1));
+    assert!(allocator.is_alive(&entity2));
+  }
+
+  #[test]
+  fn ignores_stale_frees() {
+    let mut allocator = EntityAllocator::new();
+
+    let entity1 = allocator.allocate();
+    allocator.free(&entity1);
+    let entity2 = allocator.allocate();
+
+    assert!(!allocator.free(&entity1));
+    assert!(allocator.is_alive(&entity2));
+  }
+
+  #[test]
+  fn iterates_live_entities() {
+    let mut allocator = EntityAllocator::new();
+
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+    let entity3 = allocator.allocate();
+    allocator.free(&entity2);
+
+    let live: Vec<EntityId> = allocator.iter().collect();
+
+    assert_eq!(live, vec![entity1, entity3]);
+  }
+}
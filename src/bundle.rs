@@ -0,0 +1,108 @@
+use crate::components::Component;
+use crate::entity::EntityId;
+use crate::world::World;
+
+/// A group of components inserted together. Every component is a bundle of
+/// one, and tuples of bundles are bundles, so bundles can nest.
+pub trait Bundle: Send + 'static {
+  fn insert(self, world: &mut World, entity: &EntityId);
+}
+
+impl<T: Component> Bundle for T {
+  fn insert(self, world: &mut World, entity: &EntityId) {
+    world.add(entity, self);
+  }
+}
+
+/// Optional components, for bundles whose parts depend on data.
+impl<B: Bundle> Bundle for Option<B> {
+  fn insert(self, world: &mut World, entity: &EntityId) {
+    if let Some(bundle) = self {
+      bundle.insert(world, entity);
+    }
+  }
+}
+
+macro_rules! impl_bundle_tuple {
+  ($($name:ident),+) => {
+    #[allow(non_snake_case)]
+    impl<$($name: Bundle),+> Bundle for ($($name,)+) {
+      fn insert(self, world: &mut World, entity: &EntityId) {
+        let ($($name,)+) = self;
+        $($name.insert(world, entity);)+
+      }
+    }
+  };
+}
+
+impl_bundle_tuple!(A);
+impl_bundle_tuple!(A, B);
+impl_bundle_tuple!(A, B, C);
+impl_bundle_tuple!(A, B, C, D);
+impl_bundle_tuple!(A, B, C, D, E);
+impl_bundle_tuple!(A, B, C, D, E, F);
+impl_bundle_tuple!(A, B, C, D, E, F, G);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H);
+
+/// Adds components to a freshly spawned entity, e.g.
+/// `world.spawn().with(Tag::new(TagType::Ship)).with(transform).id()`.
+pub struct EntityBuilder<'w> {
+  world: &'w mut World,
+  entity: EntityId,
+}
+
+impl<'w> EntityBuilder<'w> {
+  pub fn new(world: &'w mut World) -> Self {
+    let entity = world.create_entity();
+
+    EntityBuilder { world, entity }
+  }
+
+  pub fn with<B: Bundle>(self, bundle: B) -> Self {
+    bundle.insert(self.world, &self.entity);
+    self
+  }
+
+  pub fn id(self) -> EntityId {
+    self.entity
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::components::{Expirable, GlobalTransform, Physicsable, Transform};
+  use std::time::Duration;
+
+  struct MovingBundle {
+    transform: (Transform, GlobalTransform),
+    physics: Physicsable,
+  }
+
+  impl Bundle for MovingBundle {
+    fn insert(self, world: &mut World, entity: &EntityId) {
+      (self.transform, self.physics).insert(world, entity);
+    }
+  }
+
+  #[test]
+  fn inserts_nested_bundles() {
+    let mut world = World::new();
+
+    let moving = MovingBundle {
+      transform: (Transform::new(1., 2.), GlobalTransform::new(1., 2.)),
+      physics: Physicsable::new(0., 1.),
+    };
+
+    let entity = world
+      .spawn()
+      .with(moving)
+      .with(Expirable::new(Duration::default(), Duration::from_secs(1)))
+      .id();
+
+    assert!(world.has::<Transform>(&entity));
+    assert!(world.has::<GlobalTransform>(&entity));
+    assert!(world.has::<Physicsable>(&entity));
+    assert!(world.has::<Expirable>(&entity));
+  }
+}
@@ -0,0 +1,159 @@
+use crate::bundle::Bundle;
+use crate::components::{Component, Transform};
+use crate::entity::EntityId;
+use crate::events::Event;
+use crate::world::World;
+
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Structural changes queued by systems while they iterate the world, applied
+/// in order by `World::apply` at the sync points between systems.
+pub struct Commands {
+  queue: Vec<Command>,
+}
+
+impl Commands {
+  pub fn new() -> Self {
+    Commands { queue: vec![] }
+  }
+
+  #[allow(dead_code, reason = "no system spawns through a closure yet")]
+  pub fn spawn<F>(&mut self, spawn: F)
+  where
+    F: FnOnce(&mut World) -> EntityId + Send + 'static,
+  {
+    self.add(move |world| {
+      spawn(world);
+    });
+  }
+
+  /// Spawns a prefab. Failures are sent as `PrefabError` events rather than
+  /// failing the frame.
+  pub fn spawn_prefab(&mut self, name: &str, transform: Transform) {
+    let name = name.to_string();
+    self.add(move |world| {
+      if let Err(error) = world.spawn_prefab_at(&name, transform) {
+        world.send(error);
+      }
+    });
+  }
+
+  /// Inserts a component, or every component of a bundle, in one command.
+  #[allow(dead_code, reason = "no system inserts components yet")]
+  pub fn insert<B: Bundle>(&mut self, entity: EntityId, bundle: B) {
+    self.add(move |world| world.insert_bundle(&entity, bundle));
+  }
+
+  #[allow(dead_code, reason = "no system removes components yet")]
+  pub fn remove<T: Component>(&mut self, entity: EntityId) {
+    self.add(move |world| world.remove_component::<T>(&entity));
+  }
+
+  pub fn despawn(&mut self, entity: EntityId) {
+    self.add(move |world| world.remove(&entity));
+  }
+
+  pub fn send<E: Event>(&mut self, event: E) {
+    self.add(move |world| world.send(event));
+  }
+
+  pub fn add<F>(&mut self, command: F)
+  where
+    F: FnOnce(&mut World) + Send + 'static,
+  {
+    self.queue.push(Box::new(command));
+  }
+
+  #[cfg(test)]
+  pub fn len(&self) -> usize {
+    self.queue.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.queue.is_empty()
+  }
+
+  pub(crate) fn drain(&mut self) -> impl Iterator<Item = Command> + '_ {
+    self.queue.drain(..)
+  }
+}
+
+impl Default for Commands {
+  fn default() -> Self {
+    Commands::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::components::{Expirable, Transform};
+  use crate::events::ReaderId;
+  use crate::prefab::{PrefabError, Prefabs};
+  use crate::resources::GameClock;
+  use std::time::Duration;
+
+  #[test]
+  fn defers_changes_until_applied() {
+    let mut world = World::new();
+    let mut commands = Commands::new();
+
+    let entity1 = world.create_entity();
+    world.add(&entity1, Transform::new(0., 0.));
+    world.add(
+      &entity1,
+      Expirable::new(Duration::default(), Duration::from_secs(1)),
+    );
+    let entity2 = world.create_entity();
+    world.add(&entity2, Transform::new(1., 1.));
+
+    for (entity, _) in world.query::<&Transform>() {
+      commands.remove::<Transform>(entity);
+      commands.insert(
+        entity,
+        Expirable::new(Duration::default(), Duration::from_secs(2)),
+      );
+      commands.spawn(|world| {
+        let entity = world.create_entity();
+        world.add(&entity, Transform::new(2., 2.));
+        entity
+      });
+    }
+    commands.despawn(entity1);
+
+    assert_eq!(commands.len(), 7);
+    assert_eq!(world.components::<Transform>().len(), 2);
+
+    world.apply(&mut commands);
+
+    assert!(commands.is_empty());
+    assert!(!world.is_alive(&entity1));
+    assert!(world.get::<Transform>(&entity2).is_none());
+    assert_eq!(
+      world.get::<Expirable>(&entity2).unwrap().expiration,
+      Duration::from_secs(2)
+    );
+    assert_eq!(world.components::<Transform>().len(), 2);
+  }
+
+  #[test]
+  fn sends_failed_prefab_spawns_as_events() {
+    let mut world = World::new();
+    world.insert_resource(GameClock::new());
+    world.insert_resource(Prefabs::new());
+
+    let mut commands = Commands::new();
+    commands.spawn_prefab("rocket", Transform::new(0., 0.));
+    world.apply(&mut commands);
+
+    let errors: Vec<String> = world
+      .read::<PrefabError>(ReaderId::of::<PrefabError>())
+      .map(|error| error.to_string())
+      .collect();
+    assert_eq!(
+      errors,
+      vec![PrefabError::Unknown("rocket".to_string()).to_string()]
+    );
+    assert_eq!(world.live_entities().count(), 0);
+  }
+}
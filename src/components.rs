@@ -1,165 +1,159 @@
 use crate::entity::EntityId;
-use std::any::Any;
-use std::collections::HashMap;
+use crate::reflect::Reflect;
+use std::any::{type_name, Any};
 
 mod collidable;
-mod collision;
 mod damage;
-mod damaged;
 mod drawable;
 mod expirable;
+mod global_transform;
+mod hierarchy;
 mod physicsable;
 mod player_controllable;
+mod prefab_name;
 mod tag;
 mod transform;
 mod vulnerable;
 
 pub use collidable::{Collidable, CollisionBounds};
-pub use collision::Collision;
 pub use damage::{Damage, DamageType};
-pub use damaged::Damaged;
-pub use drawable::Drawable;
+pub use drawable::{DrawMode, Drawable};
 pub use expirable::Expirable;
+pub use global_transform::GlobalTransform;
+pub use hierarchy::{Children, Parent};
 pub use physicsable::Physicsable;
 pub use player_controllable::PlayerControllable;
-pub use tag::{Tag, TagType};
+pub use prefab_name::PrefabName;
+pub use tag::{IsShape, IsShip, Tag, TagPattern, TagType};
 pub use transform::Transform;
 pub use vulnerable::Vulnerable;
 
-pub trait Component: Any + 'static {
-  fn as_any(&self) -> &dyn Any;
-  fn as_any_mut(&mut self) -> &mut dyn Any;
+pub use super_shape_battle_derive::Component;
+
+/// How a component's storage lays out its packed array.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StorageKind {
+  /// Removal swaps the last component into the gap. Fastest, but iteration
+  /// order changes as entities come and go.
+  SparseSet,
+  /// Removal shifts later components down, so iteration follows insertion
+  /// order, e.g. to keep draw order stable.
+  Ordered,
 }
 
-type SomeComponent = Box<dyn Component>;
-
-pub struct ComponentManager {
-  entity_map: HashMap<EntityId, SomeComponent>,
+/// What the world does with a component when an entity it references is
+/// despawned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OnDespawn {
+  /// Removes the component from its entity.
+  Remove,
+  /// Drops the reference, or removes the component if the field can't be
+  /// emptied.
+  Clear,
+  /// Leaves the component alone and reports the dangling reference.
+  #[allow(dead_code, reason = "no game component asks for reports yet")]
+  Report,
 }
 
-impl ComponentManager {
-  pub fn new() -> Self {
-    ComponentManager {
-      entity_map: HashMap::new(),
-    }
-  }
+/// Field types that can be marked `#[entity]`.
+pub trait EntityRef {
+  fn collect(&self, refs: &mut Vec<EntityId>);
 
-  pub fn get<T: Component>(&self, entity: &EntityId) -> Option<&T> {
-    if let Some(component) = self.entity_map.get(&entity) {
-      return downcast::<T>(component);
-    }
+  /// Drops references to `entity`, returning false if this field can't.
+  fn clear(&mut self, entity: &EntityId) -> bool;
+}
 
-    None
+impl EntityRef for EntityId {
+  fn collect(&self, refs: &mut Vec<EntityId>) {
+    refs.push(*self);
   }
 
-  pub fn get_mut<T: Component>(&mut self, entity: &EntityId) -> Option<&mut T> {
-    if let Some(component) = self.entity_map.get_mut(&entity) {
-      return downcast_mut::<T>(component);
-    }
-
-    None
+  fn clear(&mut self, entity: &EntityId) -> bool {
+    self != entity
   }
+}
 
-  pub fn add<T: Component>(&mut self, entity: &EntityId, component: T) {
-    self.entity_map.insert(*entity, Box::new(component));
+impl EntityRef for Option<EntityId> {
+  fn collect(&self, refs: &mut Vec<EntityId>) {
+    refs.extend(self);
   }
 
-  pub fn remove(&mut self, entity: &EntityId) {
-    self.entity_map.remove(entity);
+  fn clear(&mut self, entity: &EntityId) -> bool {
+    if self.as_ref() == Some(entity) {
+      *self = None;
+    }
+    true
   }
+}
 
-  pub fn components<T: Component>(&self) -> Vec<&T> {
-    self.entity_map.values().filter_map(downcast::<T>).collect()
+impl EntityRef for Vec<EntityId> {
+  fn collect(&self, refs: &mut Vec<EntityId>) {
+    refs.extend(self);
   }
 
-  pub fn entities(&self) -> Vec<EntityId> {
-    self.entity_map.keys().cloned().collect()
+  fn clear(&mut self, entity: &EntityId) -> bool {
+    self.retain(|e| e != entity);
+    true
   }
 }
 
-fn downcast<T: Component>(c: &Box<dyn Component>) -> Option<&T> {
-  c.as_any().downcast_ref::<T>()
-}
-
-fn downcast_mut<T: Component>(c: &mut Box<dyn Component>) -> Option<&mut T> {
-  c.as_any_mut().downcast_mut::<T>()
-}
-
-#[cfg(test)]
-
-mod tests {
-  use super::*;
-  use crate::components::Transform;
-  use ggez::nalgebra::Point2;
-  use uuid::Uuid;
-
-  #[test]
-  fn can_add_and_remove_components() {
-    let transform1 = Transform::new(0., 0.);
-    let transform2 = Transform::new(1., 1.);
-    let entity1 = Uuid::new_v4();
-    let entity2 = Uuid::new_v4();
-
-    let mut component_manager = ComponentManager::new();
-
-    component_manager.add(&entity1, transform1);
-    component_manager.add(&entity2, transform2);
-
-    let transform1 = component_manager.get::<Transform>(&entity1);
-    assert!(transform1.is_some());
-    assert_eq!(transform1.unwrap().position, Point2::new(0.0f32, 0.0f32));
-
-    component_manager.remove(&entity1);
-    let transform1 = component_manager.get::<Transform>(&entity1);
-    assert!(transform1.is_none());
-
-    let transform2 = component_manager.get::<Transform>(&entity2);
-    assert!(transform2.is_some());
-    assert_eq!(transform2.unwrap().position, Point2::new(1.0f32, 1.0f32));
+/// Implemented with `#[derive(Component)]`, see the derive crate for the
+/// `#[component(..)]` attributes.
+pub trait Component: Any + Send + Sync + 'static {
+  fn name() -> &'static str
+  where
+    Self: Sized,
+  {
+    type_name::<Self>()
   }
 
-  #[test]
-  fn can_reference_entity_ids() {
-    let transform1 = Transform::new(0., 0.);
-    let transform2 = Transform::new(1., 1.);
-    let entity1 = Uuid::new_v4();
-    let entity2 = Uuid::new_v4();
-    let expected_entities = [entity1.clone(), entity2.clone()].to_vec();
-
-    let mut component_manager = ComponentManager::new();
-
-    component_manager.add(&entity1, transform1);
-    component_manager.add(&entity2, transform2);
-
-    let entities = component_manager.entities();
-
-    assert_contains_all(entities, expected_entities);
+  fn storage_kind() -> StorageKind
+  where
+    Self: Sized,
+  {
+    StorageKind::SparseSet
   }
 
-  #[test]
-  fn can_reference_components() {
-    let transform1 = Transform::new(0., 0.);
-    let transform2 = Transform::new(1., 1.);
-    let entity1 = Uuid::new_v4();
-    let entity2 = Uuid::new_v4();
-    let expected_point1 = transform1.position.clone();
-    let expected_point2 = transform2.position.clone();
+  /// Human readable contents, for components that opt in with
+  /// `#[component(debug)]`.
+  #[allow(dead_code, reason = "the game has no inspector yet")]
+  fn debug(&self) -> Option<String> {
+    None
+  }
 
-    let mut component_manager = ComponentManager::new();
+  /// Field access for components that opt in with `#[component(reflect)]`.
+  fn as_reflect(&self) -> Option<&dyn Reflect> {
+    None
+  }
 
-    component_manager.add(&entity1, transform1);
-    component_manager.add(&entity2, transform2);
+  fn as_reflect_mut(&mut self) -> Option<&mut dyn Reflect> {
+    None
+  }
 
-    let transforms: Vec<&Transform> = component_manager.components().into_iter().collect();
+  /// How the component reacts to the entities in its `#[entity]` fields
+  /// being despawned, or `None` if it has no such fields.
+  fn on_despawn() -> Option<OnDespawn>
+  where
+    Self: Sized,
+  {
+    None
+  }
 
-    assert_eq!(transforms.len(), 2);
-    assert_eq!(transforms[0].position, expected_point1);
-    assert_eq!(transforms[1].position, expected_point2);
+  fn entity_refs(&self) -> Vec<EntityId> {
+    vec![]
   }
 
-  fn assert_contains_all<T: std::cmp::PartialEq>(v1: Vec<T>, v2: Vec<T>) {
-    for v in v2 {
-      assert!(v1.contains(&v));
-    }
+  /// Drops references to `entity`, returning false if a field can't be
+  /// emptied.
+  fn clear_entity_ref(&mut self, _entity: &EntityId) -> bool {
+    true
   }
+
+  fn as_any(&self) -> &dyn Any;
 }
+
+/// Components that can be borrowed mutably through `World::get_mut` and
+/// `&mut T` queries. Derived for every component except those marked
+/// `#[component(immutable)]`, like `Tag`, and those with `#[entity]` fields,
+/// which the world indexes and only changes itself.
+pub trait Mutable: Component {}
@@ -1,12 +1,13 @@
 use geo::{LineString, Polygon};
 use ggez::nalgebra::Point2;
-use std::any::Any;
 
 use super::Component;
+use crate::reflect::Reflect;
 
 pub type CollisionBounds = Polygon<f32>;
 
-#[derive(Clone)]
+#[derive(Clone, Component, Reflect)]
+#[component(reflect)]
 pub struct Collidable {
   pub bounds: CollisionBounds,
 }
@@ -19,13 +20,3 @@ impl Collidable {
     }
   }
 }
-
-impl Component for Collidable {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
-  }
-}
@@ -1,14 +1,15 @@
-use std::any::Any;
-
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum DamageType {
   Projectile,
   Smash,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
 pub struct Damage {
   pub damage_type: DamageType,
 }
@@ -18,13 +19,3 @@ impl Damage {
     Damage { damage_type }
   }
 }
-
-impl Component for Damage {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
-  }
-}
@@ -1,27 +1,45 @@
-use ggez::graphics::Mesh;
 use ggez::nalgebra::Point2;
-use std::any::Any;
 
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+/// How a `Drawable`'s polygon is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DrawMode {
+  /// Just the outline, `line_width` wide.
+  #[default]
+  Stroke,
+  Fill,
+}
+
+/// What an entity looks like. Only a description: the draw system turns it
+/// into a mesh when the entity is first drawn.
+#[derive(Clone, Debug, PartialEq, Component, Reflect, FromReflect)]
+#[component(storage = "ordered", debug, reflect)]
 pub struct Drawable {
-  pub mesh: Mesh,
+  pub vertices: Vec<Point2<f32>>,
+  pub mode: DrawMode,
+  pub color: (f32, f32, f32, f32),
+  pub line_width: f32,
+  /// Distance from the entity's position to the polygon's origin.
   pub offset: Point2<f32>,
 }
 
 impl Drawable {
-  pub fn new(mesh: Mesh, offset: Point2<f32>) -> Self {
-    Drawable { mesh, offset }
-  }
-}
-
-impl Component for Drawable {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
+  pub fn new(
+    vertices: Vec<Point2<f32>>,
+    mode: DrawMode,
+    color: (f32, f32, f32, f32),
+    line_width: f32,
+    offset: Point2<f32>,
+  ) -> Self {
+    Drawable {
+      vertices,
+      mode,
+      color,
+      line_width,
+      offset,
+    }
   }
 }
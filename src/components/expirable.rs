@@ -1,33 +1,26 @@
-use std::any::Any;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
+use crate::resources::GameClock;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
 pub struct Expirable {
-  pub created: Instant,
+  /// `GameClock` time the entity was created.
+  pub created: Duration,
   pub expiration: Duration,
 }
 
 impl Expirable {
-  pub fn new(expiration: Duration) -> Self {
+  pub fn new(created: Duration, expiration: Duration) -> Self {
     Expirable {
-      created: Instant::now(),
+      created,
       expiration,
     }
   }
 
-  pub fn is_expired(&self) -> bool {
-    self.created.elapsed() > self.expiration
-  }
-}
-
-impl Component for Expirable {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
+  pub fn is_expired(&self, clock: &GameClock) -> bool {
+    clock.since(self.created) > self.expiration
   }
 }
@@ -0,0 +1,77 @@
+use super::Component;
+use super::Transform;
+use crate::reflect::{FromReflect, Reflect};
+use ggez::nalgebra::{Point2, Rotation2};
+
+/// World-space position and rotation, derived each step from the entity's
+/// local `Transform` and those of its parents.
+#[derive(Clone, Debug, PartialEq, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
+pub struct GlobalTransform {
+  pub position: Point2<f32>,
+  pub rotation: f32,
+  /// Position and rotation as of the previous step, for drawing between
+  /// steps.
+  #[reflect(skip)]
+  pub previous: Option<(Point2<f32>, f32)>,
+}
+
+impl GlobalTransform {
+  pub fn new(x: f32, y: f32) -> Self {
+    GlobalTransform {
+      position: Point2::new(x, y),
+      rotation: 0.0,
+      previous: None,
+    }
+  }
+
+  /// Moves to `next`, remembering where this transform was.
+  pub fn advance(&mut self, next: GlobalTransform) {
+    self.previous = Some((self.position, self.rotation));
+    self.position = next.position;
+    self.rotation = next.rotation;
+  }
+
+  /// The position and rotation `alpha` of the way from the previous step to
+  /// this one.
+  pub fn interpolate(&self, alpha: f32) -> (Point2<f32>, f32) {
+    match self.previous {
+      Some((position, rotation)) => {
+        // turn the short way round when crossing 0 degrees
+        let turn = (self.rotation - rotation + 540.0) % 360.0 - 180.0;
+        (
+          position + (self.position - position) * alpha,
+          rotation + turn * alpha,
+        )
+      }
+      None => (self.position, self.rotation),
+    }
+  }
+
+  /// Places `local`, which is relative to this transform, in world space.
+  pub fn mul_transform(&self, local: &Transform) -> GlobalTransform {
+    let rotation = Rotation2::new(self.rotation.to_radians());
+
+    GlobalTransform {
+      position: self.position + rotation * local.position.coords,
+      rotation: self.rotation + local.rotation,
+      previous: None,
+    }
+  }
+}
+
+impl From<&Transform> for GlobalTransform {
+  fn from(transform: &Transform) -> Self {
+    GlobalTransform {
+      position: transform.position,
+      rotation: transform.rotation,
+      previous: None,
+    }
+  }
+}
+
+impl Default for GlobalTransform {
+  fn default() -> Self {
+    GlobalTransform::new(0., 0.)
+  }
+}
@@ -0,0 +1,31 @@
+use super::Component;
+use crate::entity::EntityId;
+use crate::reflect::{FromReflect, Reflect};
+
+/// The entity this one is attached to. Maintained by `World::set_parent`.
+#[derive(Clone, Copy, Debug, PartialEq, Component, Reflect, FromReflect)]
+#[component(debug, reflect, on_despawn = "remove")]
+pub struct Parent {
+  #[entity]
+  pub entity: EntityId,
+}
+
+impl Parent {
+  pub fn new(entity: EntityId) -> Self {
+    Parent { entity }
+  }
+}
+
+/// Entities attached to this one. Maintained by `World::set_parent`.
+#[derive(Clone, Debug, Default, PartialEq, Component, Reflect, FromReflect)]
+#[component(debug, reflect, on_despawn = "clear")]
+pub struct Children {
+  #[entity]
+  pub entities: Vec<EntityId>,
+}
+
+impl Children {
+  pub fn new(entities: Vec<EntityId>) -> Self {
+    Children { entities }
+  }
+}
@@ -1,11 +1,14 @@
 use ggez::nalgebra::Vector2;
-use std::any::Any;
 
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
 pub struct Physicsable {
+  /// Pixels per second.
   pub velocity: Vector2<f32>,
+  /// Pixels per second per second.
   pub acceleration: Vector2<f32>,
 }
 
@@ -17,13 +20,3 @@ impl Physicsable {
     }
   }
 }
-
-impl Component for Physicsable {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
-  }
-}
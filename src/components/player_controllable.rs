@@ -1,26 +1,44 @@
 use super::Component;
-use std::any::Any;
-use std::time::{Duration, Instant};
+use crate::reflect::{FromReflect, Reflect};
+use crate::resources::GameClock;
+use std::time::Duration;
 
-#[derive(Clone)]
+#[derive(Clone, Component, Reflect, FromReflect)]
+#[component(reflect)]
 pub struct PlayerControllable {
-  pub last_fired: Instant,
+  /// `GameClock` time of the last shot.
+  pub last_fired: Option<Duration>,
 }
 
 impl PlayerControllable {
   pub fn new() -> Self {
-    PlayerControllable {
-      last_fired: Instant::now() - Duration::from_secs(5),
-    }
+    PlayerControllable { last_fired: None }
   }
-}
 
-impl Component for PlayerControllable {
-  fn as_any(&self) -> &dyn Any {
+  /// Whether `cooldown` has passed on `clock` since the last shot.
+  pub fn can_fire(&self, clock: &GameClock, cooldown: Duration) -> bool {
     self
+      .last_fired
+      .is_none_or(|last_fired| clock.since(last_fired) >= cooldown)
   }
+}
 
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn waits_out_the_cooldown_between_shots() {
+    let cooldown = Duration::from_millis(250);
+    let mut clock = GameClock::new();
+    let mut controllable = PlayerControllable::new();
+    assert!(controllable.can_fire(&clock, cooldown));
+
+    controllable.last_fired = Some(clock.elapsed());
+    clock.advance(Duration::from_millis(200));
+    assert!(!controllable.can_fire(&clock, cooldown));
+
+    clock.advance(Duration::from_millis(50));
+    assert!(controllable.can_fire(&clock, cooldown));
   }
 }
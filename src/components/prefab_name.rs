@@ -0,0 +1,18 @@
+use super::Component;
+use crate::reflect::{FromReflect, Reflect};
+
+/// The prefab an entity was spawned from, used to look up its split rules and
+/// to rebuild its collision bounds after loading a save.
+#[derive(Clone, Debug, PartialEq, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
+pub struct PrefabName {
+  pub name: String,
+}
+
+impl PrefabName {
+  pub fn new(name: &str) -> Self {
+    PrefabName {
+      name: name.to_string(),
+    }
+  }
+}
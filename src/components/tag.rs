@@ -1,9 +1,11 @@
-use std::any::Any;
 use std::fmt;
 
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Debug, Clone)]
+/// Ordered so shapes of neighbouring levels sit together in the tag index.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Serialize, Deserialize)]
 pub enum TagType {
   Ship,
   Shape(u8),
@@ -22,23 +24,73 @@ impl fmt::Display for TagType {
   }
 }
 
-#[derive(Clone)]
+/// A group of `TagType`s, for filtering queries with `Tagged<P>`.
+pub trait TagPattern: Send + Sync + 'static {
+  fn matches(tag_type: &TagType) -> bool;
+}
+
+pub struct IsShip;
+
+impl TagPattern for IsShip {
+  fn matches(tag_type: &TagType) -> bool {
+    *tag_type == TagType::Ship
+  }
+}
+
+/// Shapes of any level.
+pub struct IsShape;
+
+impl TagPattern for IsShape {
+  fn matches(tag_type: &TagType) -> bool {
+    matches!(tag_type, TagType::Shape(_))
+  }
+}
+
+/// The tags an entity carries. Change them through `World::add_tag` and
+/// `World::remove_tag`, which keep the world's tag index up to date.
+#[derive(Clone, Debug, Component, Reflect, FromReflect)]
+#[component(debug, reflect, immutable)]
 pub struct Tag {
-  pub tag_type: TagType,
+  tags: Vec<TagType>,
 }
 
 impl Tag {
   pub fn new(tag_type: TagType) -> Self {
-    Tag { tag_type }
+    Tag {
+      tags: vec![tag_type],
+    }
   }
-}
 
-impl Component for Tag {
-  fn as_any(&self) -> &dyn Any {
+  pub fn with(mut self, tag_type: TagType) -> Self {
+    self.insert(tag_type);
     self
   }
 
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
+  pub fn tags(&self) -> &[TagType] {
+    &self.tags
+  }
+
+  pub fn has(&self, tag_type: &TagType) -> bool {
+    self.tags.contains(tag_type)
+  }
+
+  pub fn matches<P: TagPattern>(&self) -> bool {
+    self.tags.iter().any(P::matches)
+  }
+
+  pub(crate) fn insert(&mut self, tag_type: TagType) {
+    if !self.has(&tag_type) {
+      self.tags.push(tag_type);
+    }
+  }
+
+  pub(crate) fn remove(&mut self, tag_type: &TagType) {
+    self.tags.retain(|t| t != tag_type);
+  }
+}
+
+impl From<Vec<TagType>> for Tag {
+  fn from(tag_types: Vec<TagType>) -> Self {
+    tag_types.into_iter().fold(Tag { tags: vec![] }, Tag::with)
   }
 }
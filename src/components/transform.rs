@@ -1,8 +1,9 @@
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
 use ggez::nalgebra::Point2;
-use std::any::Any;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
 pub struct Transform {
   pub position: Point2<f32>,
   pub rotation: f32,
@@ -17,16 +18,6 @@ impl Transform {
   }
 }
 
-impl Component for Transform {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
-  }
-}
-
 impl Default for Transform {
   fn default() -> Self {
     Transform::new(0., 0.)
@@ -1,9 +1,10 @@
 use crate::components::DamageType;
-use std::any::Any;
 
 use super::Component;
+use crate::reflect::{FromReflect, Reflect};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Component, Reflect, FromReflect)]
+#[component(debug, reflect)]
 pub struct Vulnerable {
   pub damage_types: Vec<DamageType>,
 }
@@ -13,13 +14,3 @@ impl Vulnerable {
     Vulnerable { damage_types }
   }
 }
-
-impl Component for Vulnerable {
-  fn as_any(&self) -> &dyn Any {
-    self
-  }
-
-  fn as_any_mut(&mut self) -> &mut dyn Any {
-    self
-  }
-}
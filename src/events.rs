@@ -0,0 +1,152 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+mod collision;
+mod damaged;
+
+pub use collision::CollisionEvent;
+pub use damaged::DamagedEvent;
+
+pub trait Event: Send + Sync + 'static {}
+
+/// Identifies a reader of an event channel. Each reader keeps its own cursor,
+/// so every reader sees every event exactly once.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ReaderId(TypeId);
+
+impl ReaderId {
+  pub fn of<R: 'static>() -> Self {
+    ReaderId(TypeId::of::<R>())
+  }
+}
+
+/// Type-erased view of an `Events` channel so `World` can hold one per
+/// event type.
+pub trait EventStorage: Any + Send + Sync {
+  fn update(&mut self);
+  fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Channel of events of a single type. Events are kept for the frame they
+/// were sent in and the following one, so readers that run before the sender
+/// still see them on the next frame.
+pub struct Events<E: Event> {
+  events: Vec<(usize, u64, E)>,
+  next_id: usize,
+  frame: u64,
+  readers: Mutex<HashMap<ReaderId, usize>>,
+}
+
+impl<E: Event> Events<E> {
+  pub fn new() -> Self {
+    Events {
+      events: vec![],
+      next_id: 0,
+      frame: 0,
+      readers: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub fn send(&mut self, event: E) {
+    self.events.push((self.next_id, self.frame, event));
+    self.next_id += 1;
+  }
+
+  /// Returns the events `reader` hasn't seen yet and moves its cursor past
+  /// them.
+  pub fn read(&self, reader: ReaderId) -> impl Iterator<Item = &E> {
+    let mut readers = self.readers.lock().unwrap();
+    let cursor = readers.entry(reader).or_insert(0);
+    let start = *cursor;
+    *cursor = self.next_id;
+
+    self
+      .events
+      .iter()
+      .filter(move |(id, _, _)| *id >= start)
+      .map(|(_, _, event)| event)
+  }
+
+  #[cfg(test)]
+  pub fn len(&self) -> usize {
+    self.events.len()
+  }
+
+  #[cfg(test)]
+  pub fn is_empty(&self) -> bool {
+    self.events.is_empty()
+  }
+}
+
+impl<E: Event> Default for Events<E> {
+  fn default() -> Self {
+    Events::new()
+  }
+}
+
+impl<E: Event> EventStorage for Events<E> {
+  fn update(&mut self) {
+    let frame = self.frame;
+    self.events.retain(|(_, sent, _)| *sent == frame);
+    self.frame += 1;
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct TestEvent(u32);
+  impl Event for TestEvent {}
+
+  struct Reader1;
+  struct Reader2;
+
+  fn read_all(events: &Events<TestEvent>, reader: ReaderId) -> Vec<u32> {
+    events.read(reader).map(|e| e.0).collect()
+  }
+
+  #[test]
+  fn readers_have_independent_cursors() {
+    let mut events = Events::new();
+
+    events.send(TestEvent(1));
+    assert_eq!(read_all(&events, ReaderId::of::<Reader1>()), vec![1]);
+
+    events.send(TestEvent(2));
+    assert_eq!(read_all(&events, ReaderId::of::<Reader1>()), vec![2]);
+    assert_eq!(read_all(&events, ReaderId::of::<Reader2>()), vec![1, 2]);
+    assert!(read_all(&events, ReaderId::of::<Reader2>()).is_empty());
+  }
+
+  #[test]
+  fn events_live_for_two_frames() {
+    let mut events = Events::new();
+
+    events.send(TestEvent(1));
+    events.update();
+    events.send(TestEvent(2));
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(read_all(&events, ReaderId::of::<Reader1>()), vec![1, 2]);
+
+    events.update();
+
+    assert_eq!(events.len(), 1);
+    assert!(read_all(&events, ReaderId::of::<Reader1>()).is_empty());
+
+    events.update();
+
+    assert!(events.is_empty());
+  }
+}
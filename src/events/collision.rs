@@ -0,0 +1,17 @@
+use crate::entity::EntityId;
+
+use super::Event;
+
+#[derive(Clone)]
+pub struct CollisionEvent {
+  pub entity1: EntityId,
+  pub entity2: EntityId,
+}
+
+impl CollisionEvent {
+  pub fn new(entity1: EntityId, entity2: EntityId) -> Self {
+    CollisionEvent { entity1, entity2 }
+  }
+}
+
+impl Event for CollisionEvent {}
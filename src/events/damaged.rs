@@ -0,0 +1,16 @@
+use crate::entity::EntityId;
+
+use super::Event;
+
+#[derive(Clone)]
+pub struct DamagedEvent {
+  pub entity: EntityId,
+}
+
+impl DamagedEvent {
+  pub fn new(entity: EntityId) -> Self {
+    DamagedEvent { entity }
+  }
+}
+
+impl Event for DamagedEvent {}
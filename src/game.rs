@@ -1,3 +1,10 @@
+use crate::commands::Commands;
+use crate::prefab::Prefabs;
+use crate::resources::{
+  ArenaSize, FireCooldown, FixedTime, FrameTime, GameClock, InputState, Scoreboard, TimeScale,
+};
+use crate::save::{self, SaveError};
+use crate::scheduler::{Schedule, Scheduler, Stage};
 use crate::systems::CollisionSystem;
 use crate::systems::DamageSystem;
 use crate::systems::DrawSystem;
@@ -6,45 +13,297 @@ use crate::systems::PhysicsSystem;
 use crate::systems::PlayerInputSystem;
 use crate::systems::ShapeManager;
 use crate::systems::ShipManager;
-use crate::systems::System;
+use crate::systems::TransformSystem;
 use crate::world::World;
-use ggez::event;
+use ggez::event::{self, KeyCode, KeyMods};
 use ggez::graphics;
+use ggez::nalgebra::Point2;
+use ggez::timer;
 use ggez::Context;
 use ggez::GameResult;
+use std::time::Duration;
+
+const SAVE_PATH: &str = "savegame.ron";
+
+/// Time scale toggled by the slow motion key.
+const SLOW_MOTION: f32 = 0.25;
 
 pub struct GameState {
   pub world: World,
+  commands: Commands,
+  schedule: Schedule,
 }
 
 impl GameState {
-  pub fn new() -> Self {
-    GameState {
-      world: World::new(),
+  /// A game in an `arena` of the window's size with the opening shapes and
+  /// ship spawned from `prefabs`. Nothing here needs a window, so tests run
+  /// the game headless.
+  pub fn new(arena: ArenaSize, prefabs: Prefabs) -> GameResult<Self> {
+    let mut world = World::new();
+    world.insert_resource(arena);
+    world.insert_resource(InputState::new());
+    world.insert_resource(Scoreboard::default());
+    world.insert_resource(FireCooldown::default());
+    world.insert_resource(FixedTime::default());
+    world.insert_resource(FrameTime::default());
+    world.insert_resource(GameClock::new());
+    world.insert_resource(TimeScale::default());
+
+    let schedule = Scheduler::new()
+      .with(PlayerInputSystem)
+      .with(PhysicsSystem)
+      .with(TransformSystem)
+      .with(ExpirationSystem)
+      .with(CollisionSystem)
+      .with(DamageSystem)
+      .with(ShipManager)
+      .with(ShapeManager)
+      .with(DrawSystem::new())
+      .build()?;
+
+    world.insert_resource(prefabs);
+
+    world.spawn_prefab("octagon", Point2::new(100.0, 100.0))?;
+    world.spawn_prefab("hexagon", Point2::new(500.0, 500.0))?;
+    world.spawn_prefab("square", Point2::new(300.0, 200.0))?;
+    world.spawn_prefab("ship", Point2::new(200.0, 200.0))?;
+
+    Ok(GameState {
+      world,
+      commands: Commands::new(),
+      schedule,
+    })
+  }
+
+  /// Runs the `Input` stage once in real time, then banks `elapsed`, scaled
+  /// by `TimeScale`, and runs the fixed steps it pays for.
+  pub fn advance(&mut self, elapsed: Duration) -> GameResult {
+    *self.world.resource_mut::<FrameTime>()? = FrameTime::new(elapsed);
+    self
+      .schedule
+      .run_headless(Stage::Input, &mut self.world, &mut self.commands)?;
+
+    let time_scale = self.world.resource_mut::<TimeScale>()?;
+    let elapsed = time_scale.apply(elapsed);
+    let stepped = time_scale.take_steps();
+
+    let steps = self.world.resource_mut::<FixedTime>()?.accumulate(elapsed) + stepped;
+    self.run_steps(steps)
+  }
+
+  /// Runs exactly `count` fixed steps of the simulation, without input.
+  pub fn run_steps(&mut self, count: u32) -> GameResult {
+    let step = self.world.resource::<FixedTime>()?.step;
+
+    for _ in 0..count {
+      self.world.resource_mut::<GameClock>()?.advance(step);
+      self.world.update_events();
+
+      for stage in &[Stage::Simulation, Stage::PostSimulation] {
+        self
+          .schedule
+          .run_headless(*stage, &mut self.world, &mut self.commands)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn control_time<F: FnOnce(&mut TimeScale)>(&mut self, control: F) {
+    if let Ok(time_scale) = self.world.resource_mut::<TimeScale>() {
+      control(time_scale);
+    }
+  }
+
+  /// Key presses have no caller to return errors to, so a failed save or
+  /// load is sent as a `SaveError` event instead.
+  fn send_error(&mut self, result: Result<(), SaveError>) {
+    if let Err(error) = result {
+      self.world.send(error);
     }
   }
+
+  fn run(&mut self, stage: Stage, ctx: &mut Context) -> GameResult {
+    self
+      .schedule
+      .run(stage, &mut self.world, &mut self.commands, ctx)
+  }
 }
 
 impl event::EventHandler for GameState {
   fn update(&mut self, ctx: &mut Context) -> GameResult {
-    PlayerInputSystem::update(&mut self.world, ctx)?;
-    PhysicsSystem::update(&mut self.world, ctx)?;
-    ExpirationSystem::update(&mut self.world, ctx)?;
-    CollisionSystem::update(&mut self.world, ctx)?;
-    DamageSystem::update(&mut self.world, ctx)?;
-    ShipManager::update(&mut self.world, ctx)?;
-    ShapeManager::update(&mut self.world, ctx)?;
-
-    Ok(())
+    self.advance(timer::delta(ctx))
   }
 
   fn draw(&mut self, ctx: &mut Context) -> GameResult {
     graphics::clear(ctx, graphics::BLACK);
 
-    DrawSystem::update(&mut self.world, ctx)?;
+    self.run(Stage::Render, ctx)?;
 
     graphics::present(ctx)?;
 
     Ok(())
   }
+
+  fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _: KeyMods, repeat: bool) {
+    // held keys are already pressed, and toggles and frame steps should only
+    // happen once per press
+    if repeat {
+      return;
+    }
+
+    if let Ok(input) = self.world.resource_mut::<InputState>() {
+      input.press(keycode);
+    }
+
+    match keycode {
+      KeyCode::Escape => event::quit(ctx),
+      KeyCode::P => self.control_time(TimeScale::toggle_pause),
+      KeyCode::F6 => self.control_time(TimeScale::toggle_frame_step),
+      KeyCode::Period => self.control_time(TimeScale::step),
+      KeyCode::F7 => self.control_time(|time| {
+        time.scale = if time.scale < 1.0 { 1.0 } else { SLOW_MOTION };
+      }),
+      KeyCode::F5 => {
+        let result = save::save(&self.world, SAVE_PATH);
+        self.send_error(result);
+      }
+      KeyCode::F9 => {
+        let result = save::load(&mut self.world, SAVE_PATH);
+        self.send_error(result);
+      }
+      _ => {}
+    }
+  }
+
+  fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _: KeyMods) {
+    if let Ok(input) = self.world.resource_mut::<InputState>() {
+      input.release(keycode);
+    }
+  }
+
+  fn resize_event(&mut self, ctx: &mut Context, _width: f32, _height: f32) {
+    let (width, height) = graphics::drawable_size(ctx);
+    self.world.insert_resource(ArenaSize::new(width, height));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::components::{Tag, TagType, Transform};
+  use crate::events::ReaderId;
+  use std::path::Path;
+
+  fn game() -> GameState {
+    let prefabs =
+      Prefabs::load_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/prefabs")).unwrap();
+    GameState::new(ArenaSize::new(800., 600.), prefabs).unwrap()
+  }
+
+  fn clear_shapes(game: &mut GameState) {
+    let shapes = game.world.tagged_shapes(..);
+    game.world.remove_all(shapes);
+  }
+
+  fn input(game: &mut GameState) -> &mut InputState {
+    game.world.resource_mut::<InputState>().unwrap()
+  }
+
+  #[test]
+  fn fires_bullets_that_expire() {
+    let mut game = game();
+    clear_shapes(&mut game);
+
+    input(&mut game).press(KeyCode::Space);
+    let step = game.world.resource::<FixedTime>().unwrap().step;
+    game.advance(step).unwrap();
+    input(&mut game).release(KeyCode::Space);
+
+    assert_eq!(game.world.tagged(TagType::Bullet).len(), 1);
+
+    game.run_steps(179).unwrap();
+    assert_eq!(game.world.tagged(TagType::Bullet).len(), 1);
+
+    game.run_steps(1).unwrap();
+    assert!(game.world.tagged(TagType::Bullet).is_empty());
+  }
+
+  #[test]
+  fn shapes_that_hit_the_ship_respawn_it() {
+    let mut game = game();
+    clear_shapes(&mut game);
+
+    let ship = game.world.tagged(TagType::Ship)[0];
+    game
+      .world
+      .spawn_prefab("square", Point2::new(200., 200.))
+      .unwrap();
+    game.run_steps(1).unwrap();
+
+    assert!(!game.world.is_alive(&ship));
+    assert_eq!(game.world.tagged(TagType::Ship).len(), 1);
+    assert_eq!(game.world.entities::<Tag>().len(), 2);
+  }
+
+  #[test]
+  fn handles_input_while_paused_or_frame_stepping() {
+    let mut game = game();
+    clear_shapes(&mut game);
+    let ship = game.world.tagged(TagType::Ship)[0];
+    let rotation = |game: &GameState| game.world.get::<Transform>(&ship).unwrap().rotation;
+    let start = rotation(&game);
+
+    game
+      .world
+      .resource_mut::<TimeScale>()
+      .unwrap()
+      .toggle_pause();
+    input(&mut game).press(KeyCode::D);
+    game.advance(Duration::from_millis(100)).unwrap();
+    input(&mut game).release(KeyCode::D);
+
+    assert!(rotation(&game) > start);
+    assert_eq!(game.world.resource::<GameClock>().unwrap().tick(), 0);
+
+    let time_scale = game.world.resource_mut::<TimeScale>().unwrap();
+    time_scale.toggle_pause();
+    time_scale.toggle_frame_step();
+    input(&mut game).press(KeyCode::Space);
+    game.advance(Duration::from_secs(1)).unwrap();
+
+    assert_eq!(game.world.tagged(TagType::Bullet).len(), 1);
+    assert_eq!(game.world.resource::<GameClock>().unwrap().tick(), 0);
+  }
+
+  #[test]
+  fn sends_failed_loads_as_events() {
+    let mut game = game();
+    let result = save::load(&mut game.world, "no/such/savegame.ron");
+    game.send_error(result);
+
+    let errors = game.world.read::<SaveError>(ReaderId::of::<SaveError>());
+    assert!(matches!(errors.collect::<Vec<_>>()[..], [SaveError::Io(_)]));
+    assert_eq!(game.world.tagged(TagType::Ship).len(), 1);
+  }
+
+  #[test]
+  fn runs_no_steps_while_paused() {
+    let mut game = game();
+    game
+      .world
+      .resource_mut::<TimeScale>()
+      .unwrap()
+      .toggle_pause();
+    game.advance(Duration::from_secs(1)).unwrap();
+    assert_eq!(game.world.resource::<GameClock>().unwrap().tick(), 0);
+
+    game
+      .world
+      .resource_mut::<TimeScale>()
+      .unwrap()
+      .toggle_pause();
+    game.advance(Duration::from_millis(50)).unwrap();
+    assert_eq!(game.world.resource::<GameClock>().unwrap().tick(), 3);
+  }
 }
@@ -0,0 +1,143 @@
+use crate::commands::Commands;
+use crate::components::Component;
+use crate::entity::EntityId;
+
+type Hook = Box<dyn Fn(EntityId, &dyn Component, &mut Commands) + Send + Sync>;
+
+/// When a hook fires relative to a component's lifecycle.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lifecycle {
+  /// The component was attached to an entity that didn't have one. Sees the
+  /// new component.
+  Add,
+  /// The component was overwritten. Sees the old component.
+  Replace,
+  /// The component was removed, or its entity despawned. Sees the component
+  /// on its way out.
+  Remove,
+}
+
+/// Hooks registered for one component type. Hooks can't touch the world
+/// directly while it's mid-change, so they queue commands instead, which run
+/// the next time the world applies commands.
+#[derive(Default)]
+pub struct ComponentHooks {
+  on_add: Vec<Hook>,
+  on_replace: Vec<Hook>,
+  on_remove: Vec<Hook>,
+}
+
+impl ComponentHooks {
+  pub fn new() -> Self {
+    ComponentHooks::default()
+  }
+
+  pub fn push<T: Component, F>(&mut self, lifecycle: Lifecycle, hook: F)
+  where
+    F: Fn(EntityId, &T, &mut Commands) + Send + Sync + 'static,
+  {
+    let hook: Hook = Box::new(move |entity, component, commands| {
+      if let Some(component) = component.as_any().downcast_ref::<T>() {
+        hook(entity, component, commands);
+      }
+    });
+
+    self.hooks_mut(lifecycle).push(hook);
+  }
+
+  pub fn is_empty(&self, lifecycle: Lifecycle) -> bool {
+    self.hooks(lifecycle).is_empty()
+  }
+
+  pub fn run(
+    &self,
+    lifecycle: Lifecycle,
+    entity: EntityId,
+    component: &dyn Component,
+    commands: &mut Commands,
+  ) {
+    for hook in self.hooks(lifecycle) {
+      hook(entity, component, commands);
+    }
+  }
+
+  fn hooks(&self, lifecycle: Lifecycle) -> &Vec<Hook> {
+    match lifecycle {
+      Lifecycle::Add => &self.on_add,
+      Lifecycle::Replace => &self.on_replace,
+      Lifecycle::Remove => &self.on_remove,
+    }
+  }
+
+  fn hooks_mut(&mut self, lifecycle: Lifecycle) -> &mut Vec<Hook> {
+    match lifecycle {
+      Lifecycle::Add => &mut self.on_add,
+      Lifecycle::Replace => &mut self.on_replace,
+      Lifecycle::Remove => &mut self.on_remove,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::commands::Commands;
+  use crate::components::{Expirable, Tag, TagType, Transform};
+  use crate::events::{Event, ReaderId};
+  use crate::world::World;
+  use std::time::Duration;
+
+  #[derive(Debug, PartialEq)]
+  struct Fired(&'static str, f32);
+
+  impl Event for Fired {}
+
+  struct Reader;
+
+  #[test]
+  fn runs_hooks_through_deferred_commands() {
+    let mut world = World::new();
+
+    world.on_add::<Transform, _>(|_, transform, commands| {
+      commands.send(Fired("add", transform.position.x))
+    });
+    world.on_replace::<Transform, _>(|_, transform, commands| {
+      commands.send(Fired("replace", transform.position.x))
+    });
+    world.on_remove::<Transform, _>(|entity, transform, commands| {
+      commands.send(Fired("remove", transform.position.x));
+      commands.spawn(move |world| {
+        let debris = world.create_entity();
+        world.add(&debris, Tag::new(TagType::Shape(1)));
+        world.add(
+          &debris,
+          Expirable::new(Duration::default(), Duration::from_secs(1)),
+        );
+        assert!(!world.is_alive(&entity));
+        debris
+      });
+    });
+
+    let entity = world.create_entity();
+    world.add(&entity, Transform::new(1., 0.));
+    world.add(&entity, Transform::new(2., 0.));
+    world.remove_component::<Transform>(&entity);
+    world.add(&entity, Transform::new(3., 0.));
+    world.remove(&entity);
+
+    assert!(world.entities::<Tag>().is_empty());
+    world.apply(&mut Commands::new());
+
+    let events: Vec<&Fired> = world.read::<Fired>(ReaderId::of::<Reader>()).collect();
+    assert_eq!(
+      events,
+      vec![
+        &Fired("add", 1.),
+        &Fired("replace", 1.),
+        &Fired("remove", 2.),
+        &Fired("add", 3.),
+        &Fired("remove", 3.),
+      ]
+    );
+    assert_eq!(world.entities::<Tag>().len(), 2);
+  }
+}
@@ -1,27 +1,48 @@
-use crate::entity::{Hexagon, Octagon, Ship, Square};
+use crate::prefab::Prefabs;
+use crate::resources::ArenaSize;
 use ggez::event;
+use ggez::graphics;
 use ggez::{ContextBuilder, GameResult};
+use std::env;
+use std::path::PathBuf;
 
+mod allocator;
+mod bundle;
+mod commands;
 mod components;
 mod entity;
+mod events;
 mod game;
 mod geometry;
-mod iter;
+mod hooks;
+mod mesh;
+mod prefab;
+mod query;
+mod reference_index;
+mod reflect;
+mod resources;
+mod save;
+mod scheduler;
+mod signature;
+mod storage;
 mod systems;
-mod vec;
+mod tag_index;
 mod world;
 
 use game::GameState;
 
 pub fn main() -> GameResult {
-    let cb = ContextBuilder::new("super_space_shape_battle", "wedgex");
-    let (ctx, event_loop) = &mut cb.build()?;
-    let state = &mut GameState::new();
+    let mut cb = ContextBuilder::new("super_space_shape_battle", "wedgex");
+    // ggez mounts the resources directory next to the executable, so also
+    // mount the checkout's when run through cargo
+    if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        cb = cb.add_resource_path(PathBuf::from(manifest_dir).join("resources"));
+    }
 
-    Octagon::create(&mut state.world, ctx, 100.0, 100.0)?;
-    Hexagon::create(&mut state.world, ctx, 500.0, 500.0)?;
-    Square::create(&mut state.world, ctx, 300.0, 200.0)?;
-    Ship::create(&mut state.world, ctx)?;
+    let (ctx, event_loop) = &mut cb.build()?;
+    let (width, height) = graphics::drawable_size(ctx);
+    let prefabs = Prefabs::load(ctx, "/prefabs")?;
+    let state = &mut GameState::new(ArenaSize::new(width, height), prefabs)?;
 
     event::run(ctx, event_loop, state)
 }
@@ -0,0 +1,91 @@
+use crate::components::{DrawMode, Drawable};
+use ggez::graphics::{self, Color, Mesh};
+use ggez::{Context, GameResult};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Meshes for the `Drawable`s drawn so far. Meshes live on the GPU, so only
+/// the renderer holds them, and each is built the first time its description
+/// is drawn. Entities that look alike share a mesh.
+#[derive(Default)]
+pub struct MeshCache {
+  meshes: HashMap<MeshKey, Mesh>,
+}
+
+impl MeshCache {
+  pub fn new() -> Self {
+    MeshCache::default()
+  }
+
+  pub fn get(&mut self, context: &mut Context, drawable: &Drawable) -> GameResult<&Mesh> {
+    match self.meshes.entry(MeshKey::of(drawable)) {
+      Entry::Occupied(entry) => Ok(entry.into_mut()),
+      Entry::Vacant(entry) => Ok(entry.insert(build(context, drawable)?)),
+    }
+  }
+}
+
+/// Everything about a `Drawable` that ends up in its mesh, with floats as
+/// bits so it can be hashed. The offset is applied when drawing instead.
+#[derive(PartialEq, Eq, Hash)]
+struct MeshKey {
+  vertices: Vec<(u32, u32)>,
+  mode: DrawMode,
+  color: [u32; 4],
+  line_width: u32,
+}
+
+impl MeshKey {
+  fn of(drawable: &Drawable) -> Self {
+    let (r, g, b, a) = drawable.color;
+
+    MeshKey {
+      vertices: drawable
+        .vertices
+        .iter()
+        .map(|vertex| (vertex.x.to_bits(), vertex.y.to_bits()))
+        .collect(),
+      mode: drawable.mode,
+      color: [r.to_bits(), g.to_bits(), b.to_bits(), a.to_bits()],
+      line_width: drawable.line_width.to_bits(),
+    }
+  }
+}
+
+fn build(context: &mut Context, drawable: &Drawable) -> GameResult<Mesh> {
+  let mode = match drawable.mode {
+    DrawMode::Stroke => graphics::DrawMode::stroke(drawable.line_width),
+    DrawMode::Fill => graphics::DrawMode::fill(),
+  };
+  let (r, g, b, a) = drawable.color;
+
+  Mesh::new_polygon(context, mode, &drawable.vertices, Color::new(r, g, b, a))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ggez::nalgebra::Point2;
+
+  #[test]
+  fn keys_ignore_the_offset() {
+    let square = |offset| {
+      Drawable::new(
+        vec![
+          Point2::new(0., 0.),
+          Point2::new(1., 0.),
+          Point2::new(1., 1.),
+        ],
+        DrawMode::Stroke,
+        (1., 1., 1., 1.),
+        2.,
+        offset,
+      )
+    };
+    let mut filled = square(Point2::new(0., 0.));
+    filled.mode = DrawMode::Fill;
+
+    assert!(MeshKey::of(&square(Point2::new(0., 0.))) == MeshKey::of(&square(Point2::new(5., 5.))));
+    assert!(MeshKey::of(&square(Point2::new(0., 0.))) != MeshKey::of(&filled));
+  }
+}
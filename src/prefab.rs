@@ -0,0 +1,435 @@
+use crate::bundle::Bundle;
+use crate::components::{
+  Collidable, Damage, DamageType, DrawMode, Drawable, Expirable, Physicsable, PlayerControllable,
+  PrefabName, Tag, TagType, Transform, Vulnerable,
+};
+use crate::entity::{EntityId, TransformBundle};
+use crate::events::Event;
+use crate::geometry::{self, rotation_transform};
+use crate::resources::{Resource, ResourceError};
+use crate::world::World;
+use ggez::filesystem;
+use ggez::nalgebra::Point2;
+use ggez::{Context, GameError, GameResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// An entity template loaded from a `.ron` file, named after the file stem.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Prefab {
+  pub tags: Vec<TagType>,
+  pub shape: PrefabShape,
+  #[serde(default = "white")]
+  pub color: (f32, f32, f32, f32),
+  #[serde(default)]
+  pub mode: DrawMode,
+  #[serde(default = "line_width")]
+  pub line_width: f32,
+  /// Distance from the entity's position to the shape's origin.
+  #[serde(default)]
+  pub offset: (f32, f32),
+  /// Pixels per second.
+  #[serde(default)]
+  pub velocity: (f32, f32),
+  /// Added to `velocity` in the direction the entity is spawned facing.
+  #[serde(default)]
+  pub speed: f32,
+  /// Seconds until the entity expires.
+  #[serde(default)]
+  pub lifetime: Option<f32>,
+  #[serde(default)]
+  pub damage: Option<DamageType>,
+  #[serde(default)]
+  pub vulnerable: Vec<DamageType>,
+  #[serde(default)]
+  pub controllable: bool,
+  /// What the entity breaks into when it's destroyed.
+  #[serde(default)]
+  pub split: Option<Split>,
+}
+
+fn white() -> (f32, f32, f32, f32) {
+  (1., 1., 1., 1.)
+}
+
+fn line_width() -> f32 {
+  2.
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum PrefabShape {
+  /// A regular polygon, rotated by `rotation` degrees.
+  Polygon {
+    sides: u32,
+    length: f32,
+    rotation: f32,
+  },
+  Vertices(Vec<(f32, f32)>),
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Split {
+  pub into: String,
+  pub count: u32,
+}
+
+impl Prefab {
+  pub fn points(&self) -> Vec<Point2<f32>> {
+    match &self.shape {
+      PrefabShape::Polygon {
+        sides,
+        length,
+        rotation,
+      } => polygon_points(*sides, *length, *rotation),
+      PrefabShape::Vertices(vertices) => vertices.iter().map(|&(x, y)| Point2::new(x, y)).collect(),
+    }
+  }
+
+  pub fn collidable(&self) -> Collidable {
+    Collidable::new(self.points())
+  }
+
+  pub fn drawable(&self) -> Drawable {
+    Drawable::new(
+      self.points(),
+      self.mode,
+      self.color,
+      self.line_width,
+      Point2::new(self.offset.0, self.offset.1),
+    )
+  }
+}
+
+#[derive(Debug)]
+pub enum PrefabError {
+  Io(io::Error),
+  Format(String, String),
+  Unknown(String),
+  Resource(ResourceError),
+}
+
+impl fmt::Display for PrefabError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PrefabError::Io(error) => write!(f, "could not read prefabs: {}", error),
+      PrefabError::Format(name, error) => write!(f, "invalid prefab {}: {}", name, error),
+      PrefabError::Unknown(name) => write!(f, "no prefab named {}", name),
+      PrefabError::Resource(error) => write!(f, "{}", error),
+    }
+  }
+}
+
+impl Error for PrefabError {}
+
+impl Event for PrefabError {}
+
+impl From<io::Error> for PrefabError {
+  fn from(error: io::Error) -> Self {
+    PrefabError::Io(error)
+  }
+}
+
+impl From<ResourceError> for PrefabError {
+  fn from(error: ResourceError) -> Self {
+    PrefabError::Resource(error)
+  }
+}
+
+impl From<PrefabError> for GameError {
+  fn from(error: PrefabError) -> Self {
+    match error {
+      PrefabError::Io(_) => GameError::FilesystemError(error.to_string()),
+      _ => GameError::ResourceLoadError(error.to_string()),
+    }
+  }
+}
+
+/// Every loaded prefab.
+#[derive(Default)]
+pub struct Prefabs {
+  prefabs: HashMap<String, Prefab>,
+}
+
+impl Resource for Prefabs {}
+
+impl Prefabs {
+  pub fn new() -> Self {
+    Prefabs::default()
+  }
+
+  /// Loads every `.ron` file in `dir` of ggez's resource filesystem, e.g.
+  /// `/prefabs` for the `resources/prefabs` next to the executable.
+  pub fn load(context: &mut Context, dir: &str) -> GameResult<Self> {
+    let paths: Vec<PathBuf> = filesystem::read_dir(context, dir)?.collect();
+
+    Prefabs::load_files(paths, |path| {
+      let mut text = String::new();
+      filesystem::open(context, path)?.read_to_string(&mut text)?;
+      Ok(text)
+    })
+  }
+
+  /// Loads every `.ron` file in the directory at `path` on disk, for tests
+  /// that run without a ggez context.
+  #[cfg(test)]
+  pub fn load_dir<P: AsRef<Path>>(path: P) -> Result<Self, PrefabError> {
+    let paths = std::fs::read_dir(path)?
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<Result<Vec<PathBuf>, io::Error>>()?;
+
+    Prefabs::load_files(paths, |path| Ok(std::fs::read_to_string(path)?))
+  }
+
+  fn load_files<E, F>(paths: Vec<PathBuf>, mut read: F) -> Result<Self, E>
+  where
+    E: From<PrefabError>,
+    F: FnMut(&Path) -> Result<String, E>,
+  {
+    let mut prefabs = Prefabs::new();
+
+    for path in paths {
+      if path.extension().is_none_or(|extension| extension != "ron") {
+        continue;
+      }
+
+      let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| PrefabError::Unknown(path.display().to_string()))?;
+      prefabs.parse(name, &read(&path)?)?;
+    }
+
+    prefabs.validate()?;
+
+    Ok(prefabs)
+  }
+
+  pub fn parse(&mut self, name: &str, text: &str) -> Result<(), PrefabError> {
+    let prefab = ron::from_str(text)
+      .map_err(|error| PrefabError::Format(name.to_string(), error.to_string()))?;
+    self.insert(name, prefab);
+
+    Ok(())
+  }
+
+  pub fn insert(&mut self, name: &str, prefab: Prefab) {
+    self.prefabs.insert(name.to_string(), prefab);
+  }
+
+  /// Checks that every split refers to a loaded prefab.
+  pub fn validate(&self) -> Result<(), PrefabError> {
+    match self
+      .prefabs
+      .values()
+      .filter_map(|prefab| prefab.split.as_ref())
+      .find(|split| !self.prefabs.contains_key(&split.into))
+    {
+      Some(split) => Err(PrefabError::Unknown(split.into.clone())),
+      None => Ok(()),
+    }
+  }
+
+  pub fn get(&self, name: &str) -> Result<&Prefab, PrefabError> {
+    self
+      .prefabs
+      .get(name)
+      .ok_or_else(|| PrefabError::Unknown(name.to_string()))
+  }
+
+  /// Components for a `name` spawned with `transform` at `GameClock` time
+  /// `now`.
+  pub fn bundle(
+    &self,
+    name: &str,
+    transform: Transform,
+    now: Duration,
+  ) -> Result<PrefabBundle, PrefabError> {
+    let prefab = self.get(name)?;
+
+    let (x, y) = prefab.velocity;
+    let mut physics = Physicsable::new(x, y);
+    physics.velocity += prefab.speed * geometry::angle_to_vec(transform.rotation);
+
+    Ok(PrefabBundle {
+      name: PrefabName::new(name),
+      tag: Tag::from(prefab.tags.clone()),
+      transform: TransformBundle::from(transform),
+      drawable: prefab.drawable(),
+      physics,
+      collidable: prefab.collidable(),
+      expiration: prefab
+        .lifetime
+        .map(|secs| Expirable::new(now, Duration::from_secs_f32(secs))),
+      damage: prefab.damage.clone().map(Damage::new),
+      vulnerable: Some(prefab.vulnerable.clone())
+        .filter(|types| !types.is_empty())
+        .map(Vulnerable::new),
+      controllable: Some(PlayerControllable::new()).filter(|_| prefab.controllable),
+    })
+  }
+}
+
+pub struct PrefabBundle {
+  pub name: PrefabName,
+  pub tag: Tag,
+  pub transform: TransformBundle,
+  pub drawable: Drawable,
+  pub physics: Physicsable,
+  pub collidable: Collidable,
+  pub expiration: Option<Expirable>,
+  pub damage: Option<Damage>,
+  pub vulnerable: Option<Vulnerable>,
+  pub controllable: Option<PlayerControllable>,
+}
+
+impl Bundle for PrefabBundle {
+  fn insert(self, world: &mut World, entity: &EntityId) {
+    (
+      (self.name, self.tag, self.transform),
+      self.drawable,
+      self.physics,
+      self.collidable,
+      self.expiration,
+      self.damage,
+      self.vulnerable,
+      self.controllable,
+    )
+      .insert(world, entity);
+  }
+}
+
+pub fn polygon_points(sides: u32, length: f32, rotation: f32) -> Vec<Point2<f32>> {
+  let angle = 2.0 * PI / sides as f32;
+
+  (0..=sides)
+    .map(|i| {
+      rotation_transform(
+        &Point2::new(
+          length * (angle * i as f32).cos(),
+          length * (angle * i as f32).sin(),
+        ),
+        rotation,
+      )
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::resources::GameClock;
+
+  const HEXAGON: &str = r#"(
+    tags: [Shape(2)],
+    shape: Polygon(sides: 6, length: 20.0, rotation: 60.0),
+    color: (1.0, 1.0, 0.0, 1.0),
+    velocity: (1.0, 1.0),
+    damage: Some(Smash),
+    vulnerable: [Projectile],
+    split: Some((into: "square", count: 2)),
+  )"#;
+
+  const SQUARE: &str = r#"(
+    tags: [Shape(1)],
+    shape: Vertices([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+  )"#;
+
+  fn prefabs() -> Prefabs {
+    let mut prefabs = Prefabs::new();
+    prefabs.parse("hexagon", HEXAGON).unwrap();
+    prefabs.parse("square", SQUARE).unwrap();
+    prefabs
+  }
+
+  #[test]
+  fn parses_prefabs_with_defaults() {
+    let prefabs = prefabs();
+
+    let hexagon = prefabs.get("hexagon").unwrap();
+    assert_eq!(hexagon.tags, vec![TagType::Shape(2)]);
+    assert_eq!(hexagon.points().len(), 7);
+    assert_eq!(hexagon.line_width, 2.);
+    assert_eq!(
+      hexagon.split,
+      Some(Split {
+        into: "square".to_string(),
+        count: 2
+      })
+    );
+
+    let square = prefabs.get("square").unwrap();
+    assert_eq!(square.color, (1., 1., 1., 1.));
+    assert_eq!(square.points()[2], Point2::new(1., 1.));
+    assert!(square.vulnerable.is_empty());
+    assert!(prefabs.validate().is_ok());
+  }
+
+  #[test]
+  fn spawns_prefabs() {
+    let mut world = World::new();
+    world.insert_resource(prefabs());
+    world.insert_resource(GameClock::new());
+
+    let hexagon = world.spawn_prefab("hexagon", Point2::new(5., 6.)).unwrap();
+
+    assert_eq!(
+      world.get::<Tag>(&hexagon).unwrap().tags(),
+      &[TagType::Shape(2)]
+    );
+    assert_eq!(world.get::<PrefabName>(&hexagon).unwrap().name, "hexagon");
+    assert_eq!(
+      world.get::<Transform>(&hexagon).unwrap().position,
+      Point2::new(5., 6.)
+    );
+    assert!(world.has::<Collidable>(&hexagon));
+    assert!(world.has::<Damage>(&hexagon));
+    assert!(world.has::<Vulnerable>(&hexagon));
+    assert_eq!(
+      world.get::<Drawable>(&hexagon).unwrap().vertices,
+      prefabs().get("hexagon").unwrap().points()
+    );
+    assert!(!world.has::<Expirable>(&hexagon));
+
+    let square = world.spawn_prefab("square", Point2::new(0., 0.)).unwrap();
+    assert!(!world.has::<Damage>(&square));
+    assert!(!world.has::<Vulnerable>(&square));
+
+    assert_eq!(
+      world
+        .spawn_prefab("triangle", Point2::new(0., 0.))
+        .unwrap_err()
+        .to_string(),
+      "no prefab named triangle"
+    );
+  }
+
+  #[test]
+  fn rejects_splits_into_unknown_prefabs() {
+    let mut prefabs = Prefabs::new();
+    prefabs.parse("hexagon", HEXAGON).unwrap();
+
+    assert_eq!(
+      prefabs.validate().unwrap_err().to_string(),
+      "no prefab named square"
+    );
+  }
+
+  #[test]
+  fn loads_the_game_prefabs() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/prefabs");
+    let prefabs = Prefabs::load_dir(path).unwrap();
+
+    for name in &["ship", "bullet", "octagon", "hexagon", "square"] {
+      assert!(prefabs.get(name).is_ok(), "missing prefab {}", name);
+    }
+    assert!(prefabs.get("ship").unwrap().controllable);
+    assert_eq!(prefabs.get("bullet").unwrap().lifetime, Some(3.));
+  }
+}
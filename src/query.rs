@@ -0,0 +1,698 @@
+use crate::components::{Component, Mutable, Tag, TagPattern};
+use crate::entity::EntityId;
+use crate::storage::{slot_of, ComponentStorage, ComponentTicks, EntityIter};
+use crate::world::World;
+use std::any::{type_name, TypeId};
+use std::marker::PhantomData;
+use std::vec;
+
+pub struct Access {
+  pub type_id: TypeId,
+  pub name: &'static str,
+  pub mutable: bool,
+}
+
+impl Access {
+  fn of<T: Component>(mutable: bool) -> Self {
+    Access {
+      type_id: TypeId::of::<T>(),
+      name: type_name::<T>(),
+      mutable,
+    }
+  }
+}
+
+/// Change ticks a query runs with. `last_run` is the tick the querying system
+/// last looked at, and `current` is stamped on every mutable borrow.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ticks {
+  pub last_run: u32,
+  pub current: u32,
+}
+
+/// A set of component references that can be fetched together for a single
+/// entity, e.g. `&Transform`, `&mut Physicsable` or a tuple of them.
+pub trait Query {
+  type Item<'w>;
+  type Fetch<'w>;
+
+  /// The components this query borrows. Filters that only check presence or
+  /// ticks go through `FetchFilter` and never borrow a component, so they
+  /// don't register any.
+  fn access(access: &mut Vec<Access>);
+
+  /// # Safety
+  /// `world` must be valid for `'w` and the access of this query must not
+  /// alias with any other live borrow of the world.
+  unsafe fn fetch<'w>(world: *mut World, ticks: Ticks) -> Option<Self::Fetch<'w>>;
+
+  /// The entities worth checking, or `None` if this query doesn't narrow
+  /// them down, as with `Without<T>`.
+  fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>>;
+
+  /// # Safety
+  /// Each entity must only be fetched once per `Fetch`.
+  unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>>;
+
+  /// Stamps the mutable borrows of `entity` as changed. Called only once the
+  /// whole query has matched, so entities a later filter rejects aren't
+  /// reported by `Changed`.
+  ///
+  /// # Safety
+  /// `entity` must have just been fetched by `get` on the same `Fetch`.
+  unsafe fn mark<'w>(_fetch: &Self::Fetch<'w>, _entity: &EntityId) {}
+}
+
+impl<T: Component> Query for &T {
+  type Item<'w> = &'w T;
+  type Fetch<'w> = &'w ComponentStorage<T>;
+
+  fn access(access: &mut Vec<Access>) {
+    access.push(Access::of::<T>(false));
+  }
+
+  unsafe fn fetch<'w>(world: *mut World, _: Ticks) -> Option<Self::Fetch<'w>> {
+    (*world).storage::<T>()
+  }
+
+  fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+    Some(fetch.entity_iter())
+  }
+
+  unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+    fetch.get(entity)
+  }
+}
+
+pub struct FetchMut<'w, T> {
+  sparse: &'w [Option<usize>],
+  entities: &'w [EntityId],
+  components: *mut T,
+  ticks: *mut ComponentTicks,
+  tick: u32,
+}
+
+impl<T: Mutable> Query for &mut T {
+  type Item<'w> = &'w mut T;
+  type Fetch<'w> = FetchMut<'w, T>;
+
+  fn access(access: &mut Vec<Access>) {
+    access.push(Access::of::<T>(true));
+  }
+
+  unsafe fn fetch<'w>(world: *mut World, ticks: Ticks) -> Option<Self::Fetch<'w>> {
+    (*world).storage_mut::<T>().map(|storage| {
+      let (sparse, entities, components, component_ticks) = storage.split_mut();
+      FetchMut {
+        sparse,
+        entities,
+        components,
+        ticks: component_ticks,
+        tick: ticks.current,
+      }
+    })
+  }
+
+  fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+    Some(fetch.entities.iter())
+  }
+
+  unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+    slot_of(fetch.sparse, fetch.entities, entity).map(|slot| &mut *fetch.components.add(slot))
+  }
+
+  unsafe fn mark<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) {
+    if let Some(slot) = slot_of(fetch.sparse, fetch.entities, entity) {
+      (*fetch.ticks.add(slot)).changed = fetch.tick;
+    }
+  }
+}
+
+/// What a filter sees of a storage: which entities have the component, and
+/// its ticks read through a raw pointer, so a filter never borrows the packed
+/// arrays a `&mut T` in the same query may be handing out.
+pub struct FetchFilter<'w> {
+  sparse: &'w [Option<usize>],
+  entities: &'w [EntityId],
+  #[allow(dead_code, reason = "only read by the `Added` and `Changed` filters")]
+  ticks: *const ComponentTicks,
+}
+
+impl<'w> FetchFilter<'w> {
+  fn of<T: Component>(storage: &'w ComponentStorage<T>) -> Self {
+    let (sparse, entities, ticks) = storage.split();
+    FetchFilter {
+      sparse,
+      entities,
+      ticks,
+    }
+  }
+
+  fn contains(&self, entity: &EntityId) -> bool {
+    slot_of(self.sparse, self.entities, entity).is_some()
+  }
+
+  /// # Safety
+  /// Nothing may be writing `entity`'s ticks at the same time.
+  #[allow(dead_code, reason = "only read by the `Added` and `Changed` filters")]
+  unsafe fn ticks(&self, entity: &EntityId) -> Option<ComponentTicks> {
+    slot_of(self.sparse, self.entities, entity).map(|slot| *self.ticks.add(slot))
+  }
+}
+
+/// Matches entities whose `T` was inserted since the query's last run.
+#[allow(dead_code, reason = "no system reacts to added components yet")]
+pub struct Added<T>(PhantomData<T>);
+
+/// Matches entities whose `T` was inserted or mutably borrowed since the
+/// query's last run.
+#[allow(dead_code, reason = "no system reacts to changed components yet")]
+pub struct Changed<T>(PhantomData<T>);
+
+macro_rules! impl_change_filter {
+  ($filter:ident, $check:ident) => {
+    impl<T: Component> Query for $filter<T> {
+      type Item<'w> = ();
+      type Fetch<'w> = (FetchFilter<'w>, u32);
+
+      fn access(_: &mut Vec<Access>) {}
+
+      unsafe fn fetch<'w>(world: *mut World, ticks: Ticks) -> Option<Self::Fetch<'w>> {
+        (*world)
+          .storage::<T>()
+          .map(|storage| (FetchFilter::of(storage), ticks.last_run))
+      }
+
+      fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+        Some(fetch.0.entities.iter())
+      }
+
+      unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+        let (filter, last_run) = fetch;
+        match filter.ticks(entity) {
+          Some(ticks) if ticks.$check(*last_run) => Some(()),
+          _ => None,
+        }
+      }
+    }
+  };
+}
+
+impl_change_filter!(Added, is_added);
+impl_change_filter!(Changed, is_changed);
+
+/// Matches entities that have a `T`, without borrowing it.
+pub struct With<T>(PhantomData<T>);
+
+impl<T: Component> Query for With<T> {
+  type Item<'w> = ();
+  type Fetch<'w> = FetchFilter<'w>;
+
+  fn access(_: &mut Vec<Access>) {}
+
+  unsafe fn fetch<'w>(world: *mut World, _: Ticks) -> Option<Self::Fetch<'w>> {
+    (*world).storage::<T>().map(FetchFilter::of)
+  }
+
+  fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+    Some(fetch.entities.iter())
+  }
+
+  unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+    fetch.contains(entity).then_some(())
+  }
+}
+
+/// Matches entities that don't have a `T`.
+pub struct Without<T>(PhantomData<T>);
+
+impl<T: Component> Query for Without<T> {
+  type Item<'w> = ();
+  type Fetch<'w> = Option<FetchFilter<'w>>;
+
+  fn access(_: &mut Vec<Access>) {}
+
+  unsafe fn fetch<'w>(world: *mut World, _: Ticks) -> Option<Self::Fetch<'w>> {
+    Some((*world).storage::<T>().map(FetchFilter::of))
+  }
+
+  fn entities<'w>(_: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+    None
+  }
+
+  unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+    match fetch {
+      Some(filter) if filter.contains(entity) => None,
+      _ => Some(()),
+    }
+  }
+}
+
+/// Matches entities that match any of the queries in the tuple `Q`, e.g.
+/// `Or<(With<Damage>, With<Vulnerable>)>`.
+#[allow(dead_code, reason = "no system combines filters yet")]
+pub struct Or<Q>(PhantomData<Q>);
+
+macro_rules! impl_or_tuple {
+  ($($name:ident),+) => {
+    #[allow(non_snake_case)]
+    impl<$($name: Query),+> Query for Or<($($name,)+)> {
+      type Item<'w> = ();
+      type Fetch<'w> = ($(Option<$name::Fetch<'w>>,)+);
+
+      fn access(access: &mut Vec<Access>) {
+        $($name::access(access);)+
+      }
+
+      unsafe fn fetch<'w>(world: *mut World, ticks: Ticks) -> Option<Self::Fetch<'w>> {
+        Some(($($name::fetch(world, ticks),)+))
+      }
+
+      fn entities<'w>(_: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+        None
+      }
+
+      unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+        let ($($name,)+) = fetch;
+        $(
+          if let Some(fetch) = $name {
+            if $name::get(fetch, entity).is_some() {
+              return Some(());
+            }
+          }
+        )+
+
+        None
+      }
+    }
+  };
+}
+
+impl_or_tuple!(A, B);
+impl_or_tuple!(A, B, C);
+impl_or_tuple!(A, B, C, D);
+
+/// Matches entities whose `Tag` fits the pattern `P`, e.g. `Tagged<IsShape>`
+/// for shapes of any level.
+pub struct Tagged<P>(PhantomData<P>);
+
+impl<P: TagPattern> Query for Tagged<P> {
+  type Item<'w> = ();
+  type Fetch<'w> = &'w ComponentStorage<Tag>;
+
+  /// Reads each `Tag`, unlike the other filters.
+  fn access(access: &mut Vec<Access>) {
+    access.push(Access::of::<Tag>(false));
+  }
+
+  unsafe fn fetch<'w>(world: *mut World, _: Ticks) -> Option<Self::Fetch<'w>> {
+    (*world).storage::<Tag>()
+  }
+
+  fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+    Some(fetch.entity_iter())
+  }
+
+  unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+    fetch
+      .get(entity)
+      .filter(|tag| tag.matches::<P>())
+      .map(|_| ())
+  }
+}
+
+macro_rules! impl_query_tuple {
+  ($($name:ident),+) => {
+    #[allow(non_snake_case)]
+    impl<$($name: Query),+> Query for ($($name,)+) {
+      type Item<'w> = ($($name::Item<'w>,)+);
+      type Fetch<'w> = ($($name::Fetch<'w>,)+);
+
+      fn access(access: &mut Vec<Access>) {
+        $($name::access(access);)+
+      }
+
+      unsafe fn fetch<'w>(world: *mut World, ticks: Ticks) -> Option<Self::Fetch<'w>> {
+        Some(($($name::fetch(world, ticks)?,)+))
+      }
+
+      fn entities<'w>(fetch: &Self::Fetch<'w>) -> Option<EntityIter<'w>> {
+        let ($($name,)+) = fetch;
+        let mut entities: Option<EntityIter<'w>> = None;
+
+        // drive the iteration from the smallest storage
+        $(
+          entities = match (entities, $name::entities($name)) {
+            (Some(current), Some(candidate)) if candidate.len() < current.len() => Some(candidate),
+            (None, candidate) => candidate,
+            (current, _) => current,
+          };
+        )+
+
+        entities
+      }
+
+      unsafe fn get<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) -> Option<Self::Item<'w>> {
+        let ($($name,)+) = fetch;
+        Some(($($name::get($name, entity)?,)+))
+      }
+
+      unsafe fn mark<'w>(fetch: &Self::Fetch<'w>, entity: &EntityId) {
+        let ($($name,)+) = fetch;
+        $($name::mark($name, entity);)+
+      }
+    }
+  };
+}
+
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, F);
+
+/// The entities a query checks: a storage's entities, or every live entity
+/// when the query is only made of filters like `Without<T>`.
+enum Candidates<'w> {
+  Stored(EntityIter<'w>),
+  Live(vec::IntoIter<EntityId>),
+}
+
+impl Iterator for Candidates<'_> {
+  type Item = EntityId;
+
+  fn next(&mut self) -> Option<EntityId> {
+    match self {
+      Candidates::Stored(entities) => entities.next().copied(),
+      Candidates::Live(entities) => entities.next(),
+    }
+  }
+}
+
+pub struct QueryIter<'w, Q: Query> {
+  fetch: Option<Q::Fetch<'w>>,
+  entities: Option<Candidates<'w>>,
+}
+
+impl<'w, Q: Query> QueryIter<'w, Q> {
+  pub fn new(world: &'w mut World, ticks: Ticks) -> Self {
+    validate_access::<Q>();
+
+    let world: *mut World = world;
+    let fetch = unsafe { Q::fetch(world, ticks) };
+    let entities = fetch.as_ref().map(|fetch| match Q::entities(fetch) {
+      Some(entities) => Candidates::Stored(entities),
+      None => {
+        let live: Vec<EntityId> = unsafe { (*world).live_entities().collect() };
+        Candidates::Live(live.into_iter())
+      }
+    });
+
+    QueryIter { fetch, entities }
+  }
+}
+
+impl<'w, Q: Query> Iterator for QueryIter<'w, Q> {
+  type Item = (EntityId, Q::Item<'w>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let fetch = self.fetch.as_ref()?;
+    let entities = self.entities.as_mut()?;
+
+    for entity in entities {
+      if let Some(item) = unsafe { Q::get(fetch, &entity) } {
+        unsafe { Q::mark(fetch, &entity) };
+        return Some((entity, item));
+      }
+    }
+
+    None
+  }
+}
+
+/// Fetches `Q` for a single entity, for checking entities that come from
+/// events rather than from iterating storages.
+pub fn fetch_one<'w, Q: Query>(
+  world: &'w mut World,
+  ticks: Ticks,
+  entity: &EntityId,
+) -> Option<Q::Item<'w>> {
+  validate_access::<Q>();
+
+  unsafe {
+    let fetch = Q::fetch(world, ticks)?;
+    let item = Q::get(&fetch, entity)?;
+    Q::mark(&fetch, entity);
+    Some(item)
+  }
+}
+
+fn validate_access<Q: Query>() {
+  let mut access = vec![];
+  Q::access(&mut access);
+
+  for (i, a) in access.iter().enumerate() {
+    for b in &access[i + 1..] {
+      if a.type_id == b.type_id && (a.mutable || b.mutable) {
+        panic!("query borrows {} mutably more than once", a.name);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::components::{
+    Damage, DamageType, Expirable, IsShape, Physicsable, TagType, Transform,
+  };
+  use crate::events::ReaderId;
+  use ggez::nalgebra::Point2;
+  use std::time::Duration;
+
+  #[test]
+  fn yields_entities_with_every_component() {
+    let mut world = World::new();
+
+    let entity1 = world.create_entity();
+    let entity2 = world.create_entity();
+
+    world.add(&entity1, Transform::new(0., 0.));
+    world.add(&entity1, Physicsable::new(1., 2.));
+    world.add(&entity2, Transform::new(1., 1.));
+
+    let results: Vec<EntityId> = world
+      .query::<(&Transform, &Physicsable)>()
+      .map(|(entity, _)| entity)
+      .collect();
+
+    assert_eq!(results, vec![entity1]);
+  }
+
+  #[test]
+  fn can_mix_shared_and_mutable_access() {
+    let mut world = World::new();
+
+    let entity = world.create_entity();
+    world.add(&entity, Transform::new(0., 0.));
+    world.add(&entity, Physicsable::new(1., 2.));
+
+    for (_, (transform, physics)) in world.query::<(&mut Transform, &Physicsable)>() {
+      transform.position += physics.velocity;
+    }
+
+    let transform = world.get::<Transform>(&entity).unwrap();
+    assert_eq!(transform.position.x, 1.);
+    assert_eq!(transform.position.y, 2.);
+  }
+
+  #[test]
+  fn is_empty_when_a_component_is_missing() {
+    let mut world = World::new();
+
+    let entity = world.create_entity();
+    world.add(
+      &entity,
+      Expirable::new(Duration::default(), Duration::from_secs(1)),
+    );
+
+    assert_eq!(world.query::<(&Expirable, &Transform)>().count(), 0);
+  }
+
+  #[test]
+  #[should_panic]
+  fn panics_on_aliased_mutable_access() {
+    let mut world = World::new();
+
+    world.query::<(&mut Transform, &Transform)>().count();
+  }
+
+  #[test]
+  fn filters_added_and_changed_components_since_the_last_run() {
+    struct Spawner;
+    struct Mover;
+
+    let mut world = World::new();
+
+    let entity1 = world.create_entity();
+    world.add(&entity1, Transform::new(0., 0.));
+
+    let added: Vec<EntityId> = world
+      .query_since::<Added<Transform>>(ReaderId::of::<Spawner>())
+      .map(|(entity, _)| entity)
+      .collect();
+    assert_eq!(added, vec![entity1]);
+
+    let entity2 = world.create_entity();
+    world.add(&entity2, Transform::new(1., 1.));
+    world.get_mut::<Transform>(&entity1).unwrap().position.x = 2.;
+
+    let added: Vec<EntityId> = world
+      .query_since::<Added<Transform>>(ReaderId::of::<Spawner>())
+      .map(|(entity, _)| entity)
+      .collect();
+    assert_eq!(added, vec![entity2]);
+
+    let changed = world
+      .query_since::<(&Transform, Changed<Transform>)>(ReaderId::of::<Mover>())
+      .count();
+    assert_eq!(changed, 2);
+
+    world.get_mut::<Transform>(&entity1).unwrap().position.y = 1.;
+    world.get::<Transform>(&entity2);
+
+    let changed: Vec<EntityId> = world
+      .query_since::<(&Transform, Changed<Transform>)>(ReaderId::of::<Mover>())
+      .map(|(entity, _)| entity)
+      .collect();
+    assert_eq!(changed, vec![entity1]);
+    assert_eq!(
+      world
+        .query_since::<Added<Transform>>(ReaderId::of::<Spawner>())
+        .count(),
+      0
+    );
+  }
+
+  #[test]
+  fn only_marks_entities_the_whole_query_matches() {
+    struct Watcher;
+
+    let mut world = World::new();
+    let moving = world.create_entity();
+    world.add(&moving, Transform::new(0., 0.));
+    let physical = world.create_entity();
+    world.add(&physical, Transform::new(0., 0.));
+    world.add(&physical, Physicsable::new(1., 1.));
+
+    world
+      .query_since::<Changed<Transform>>(ReaderId::of::<Watcher>())
+      .count();
+    world
+      .query::<(&mut Transform, Without<Physicsable>)>()
+      .count();
+    world.query::<(&mut Transform, &Damage)>().count();
+
+    let changed: Vec<EntityId> = world
+      .query_since::<Changed<Transform>>(ReaderId::of::<Watcher>())
+      .map(|(entity, _)| entity)
+      .collect();
+    assert_eq!(changed, vec![moving]);
+  }
+
+  #[test]
+  fn mutates_components_a_filter_checks() {
+    struct Mover;
+
+    let mut world = World::new();
+    let entity = world.create_entity();
+    world.add(&entity, Transform::new(0., 0.));
+
+    for (_, (transform, _)) in
+      world.query_since::<(&mut Transform, Changed<Transform>)>(ReaderId::of::<Mover>())
+    {
+      transform.position.x += 1.;
+    }
+    for (_, (transform, _)) in world.query::<(&mut Transform, With<Transform>)>() {
+      transform.position.y += 1.;
+    }
+
+    assert_eq!(
+      world.get::<Transform>(&entity).unwrap().position,
+      Point2::new(1., 1.)
+    );
+    assert_eq!(
+      world
+        .query_since::<(&mut Transform, Changed<Transform>)>(ReaderId::of::<Mover>())
+        .count(),
+      1
+    );
+  }
+
+  fn entities<Q: Query>(world: &mut World) -> Vec<EntityId> {
+    world.query::<Q>().map(|(entity, _)| entity).collect()
+  }
+
+  #[test]
+  fn filters_by_component_presence() {
+    let mut world = World::new();
+
+    let entity1 = world.create_entity();
+    world.add(&entity1, Transform::new(0., 0.));
+    world.add(&entity1, Physicsable::new(1., 2.));
+    let entity2 = world.create_entity();
+    world.add(&entity2, Transform::new(1., 1.));
+    let entity3 = world.create_entity();
+    world.add(
+      &entity3,
+      Expirable::new(Duration::default(), Duration::from_secs(1)),
+    );
+
+    assert_eq!(
+      entities::<(&Transform, With<Physicsable>)>(&mut world),
+      vec![entity1]
+    );
+    assert_eq!(
+      entities::<(&Transform, Without<Physicsable>)>(&mut world),
+      vec![entity2]
+    );
+    assert_eq!(entities::<Without<Transform>>(&mut world), vec![entity3]);
+    assert_eq!(
+      entities::<(&Transform, Without<Damage>)>(&mut world),
+      vec![entity1, entity2]
+    );
+
+    let mut either = entities::<Or<(With<Physicsable>, With<Expirable>)>>(&mut world);
+    either.sort();
+    assert_eq!(either, vec![entity1, entity3]);
+  }
+
+  #[test]
+  fn filters_by_tag_pattern() {
+    let mut world = World::new();
+
+    let ship = world.create_entity();
+    world.add(&ship, Tag::new(TagType::Ship));
+    let square = world.create_entity();
+    world.add(&square, Tag::new(TagType::Shape(1)));
+    world.add(&square, Damage::new(DamageType::Smash));
+    let octagon = world.create_entity();
+    world.add(&octagon, Tag::new(TagType::Shape(3)));
+
+    assert_eq!(
+      entities::<(&Tag, Tagged<IsShape>)>(&mut world),
+      vec![square, octagon]
+    );
+    assert!(world
+      .query_one::<(&Damage, Tagged<IsShape>)>(&square)
+      .is_some());
+    assert!(world
+      .query_one::<(&Damage, Tagged<IsShape>)>(&octagon)
+      .is_none());
+    assert!(world.query_one::<Tagged<IsShape>>(&ship).is_none());
+
+    world.remove(&square);
+    assert!(world.query_one::<Without<Damage>>(&square).is_none());
+  }
+}
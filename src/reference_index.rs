@@ -0,0 +1,57 @@
+use crate::entity::EntityId;
+use std::collections::{BTreeSet, HashMap};
+
+/// The components pointing at each entity, kept up to date by `World` as
+/// components with `#[entity]` fields are added, changed and removed, so a
+/// despawn only visits the components that actually reference it.
+#[derive(Default)]
+pub struct ReferenceIndex {
+  /// Target to the (entity, component id) pairs referencing it.
+  referrers: HashMap<EntityId, BTreeSet<(EntityId, usize)>>,
+  /// What each indexed component points at, to unindex it again.
+  targets: HashMap<(EntityId, usize), Vec<EntityId>>,
+}
+
+impl ReferenceIndex {
+  pub fn new() -> Self {
+    ReferenceIndex::default()
+  }
+
+  /// Records that `entity`'s component `id` now references `targets`,
+  /// replacing whatever it referenced before.
+  pub fn insert(&mut self, entity: EntityId, id: usize, targets: Vec<EntityId>) {
+    self.remove(&entity, id);
+    if targets.is_empty() {
+      return;
+    }
+
+    for target in &targets {
+      self
+        .referrers
+        .entry(*target)
+        .or_default()
+        .insert((entity, id));
+    }
+    self.targets.insert((entity, id), targets);
+  }
+
+  pub fn remove(&mut self, entity: &EntityId, id: usize) {
+    for target in self.targets.remove(&(*entity, id)).unwrap_or_default() {
+      if let Some(referrers) = self.referrers.get_mut(&target) {
+        referrers.remove(&(*entity, id));
+        if referrers.is_empty() {
+          self.referrers.remove(&target);
+        }
+      }
+    }
+  }
+
+  /// Removes and returns the (entity, component id) pairs referencing
+  /// `target`, in id order.
+  pub fn take(&mut self, target: &EntityId) -> Vec<(EntityId, usize)> {
+    self
+      .referrers
+      .remove(target)
+      .map_or(vec![], |referrers| referrers.into_iter().collect())
+  }
+}
@@ -0,0 +1,253 @@
+use crate::components::Component;
+use crate::entity::EntityId;
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+mod values;
+
+pub use super_shape_battle_derive::{FromReflect, Reflect};
+
+/// A field value in a form tools can display, edit and save without knowing
+/// the component type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+  Bool(bool),
+  Int(i64),
+  Float(f32),
+  Text(String),
+  Entity(EntityId),
+  List(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::Int(i) => write!(f, "{}", i),
+      Value::Float(x) => write!(f, "{}", x),
+      Value::Text(text) => write!(f, "{:?}", text),
+      Value::Entity(entity) => write!(f, "{}", entity),
+      Value::List(values) => {
+        write!(f, "[")?;
+        for (i, value) in values.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}", value)?;
+        }
+        write!(f, "]")
+      }
+    }
+  }
+}
+
+/// Conversion between a field's type and `Value`.
+pub trait FieldValue: Sized {
+  fn to_value(&self) -> Value;
+  fn from_value(value: &Value) -> Option<Self>;
+}
+
+/// Reflected fields by name.
+pub type FieldMap = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectError {
+  UnknownComponent(String),
+  UnknownField(String),
+  MissingField(String),
+  InvalidValue(String),
+}
+
+impl fmt::Display for ReflectError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ReflectError::UnknownComponent(name) => write!(f, "no component named {}", name),
+      ReflectError::UnknownField(field) => write!(f, "no field named {}", field),
+      ReflectError::MissingField(field) => write!(f, "missing field {}", field),
+      ReflectError::InvalidValue(field) => write!(f, "invalid value for field {}", field),
+    }
+  }
+}
+
+impl Error for ReflectError {}
+
+/// Generic read/write access to a component's fields, implemented with
+/// `#[derive(Reflect)]`. Fields marked `#[reflect(skip)]` are left out.
+pub trait Reflect {
+  fn fields(&self) -> Vec<(&'static str, Value)>;
+
+  #[allow(dead_code, reason = "the game has no inspector yet")]
+  fn set_field(&mut self, name: &str, value: &Value) -> Result<(), ReflectError>;
+
+  #[allow(dead_code, reason = "the game has no inspector yet")]
+  fn field(&self, name: &str) -> Option<Value> {
+    self
+      .fields()
+      .into_iter()
+      .find(|(field, _)| *field == name)
+      .map(|(_, value)| value)
+  }
+}
+
+/// Builds a value from reflected fields, implemented with
+/// `#[derive(FromReflect)]`. Skipped fields start from their `Default`.
+pub trait FromReflect: Sized {
+  fn from_fields(fields: &FieldMap) -> Result<Self, ReflectError>;
+}
+
+type Inserter = fn(&mut World, &EntityId, &FieldMap) -> Result<(), ReflectError>;
+type Validator = fn(&FieldMap) -> Result<(), ReflectError>;
+
+/// Component types that can be rebuilt from their reflected fields, looked up
+/// by registered name.
+#[derive(Default)]
+pub struct ReflectRegistry {
+  inserters: HashMap<&'static str, (Inserter, Validator)>,
+}
+
+impl ReflectRegistry {
+  pub fn new() -> Self {
+    ReflectRegistry::default()
+  }
+
+  pub fn register<T: Component + FromReflect>(mut self) -> Self {
+    self
+      .inserters
+      .insert(T::name(), (insert_reflected::<T>, validate_reflected::<T>));
+    self
+  }
+
+  /// Builds the component called `name` from `fields` and adds it to `entity`.
+  pub fn insert(
+    &self,
+    world: &mut World,
+    entity: &EntityId,
+    name: &str,
+    fields: &FieldMap,
+  ) -> Result<(), ReflectError> {
+    let (insert, _) = self.registration(name)?;
+    insert(world, entity, fields)
+  }
+
+  /// Checks that `insert` would succeed, without touching a world.
+  pub fn validate(&self, name: &str, fields: &FieldMap) -> Result<(), ReflectError> {
+    let (_, validate) = self.registration(name)?;
+    validate(fields)
+  }
+
+  fn registration(&self, name: &str) -> Result<&(Inserter, Validator), ReflectError> {
+    self
+      .inserters
+      .get(name)
+      .ok_or_else(|| ReflectError::UnknownComponent(name.to_string()))
+  }
+}
+
+fn insert_reflected<T: Component + FromReflect>(
+  world: &mut World,
+  entity: &EntityId,
+  fields: &FieldMap,
+) -> Result<(), ReflectError> {
+  world.add(entity, T::from_fields(fields)?);
+  Ok(())
+}
+
+fn validate_reflected<T: FromReflect>(fields: &FieldMap) -> Result<(), ReflectError> {
+  T::from_fields(fields).map(|_| ())
+}
+
+/// Formats reflected fields as `name { field: value, .. }`.
+#[allow(dead_code, reason = "the game has no inspector yet")]
+pub fn describe(name: &str, reflect: &dyn Reflect) -> String {
+  let fields: Vec<String> = reflect
+    .fields()
+    .iter()
+    .map(|(field, value)| format!("{}: {}", field, value))
+    .collect();
+
+  format!("{} {{ {} }}", name, fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::components::{Physicsable, Tag, TagType, Transform};
+  use ggez::nalgebra::Vector2;
+
+  #[test]
+  fn reads_and_writes_fields_by_name() {
+    let mut transform = Transform::new(1., 2.);
+
+    assert_eq!(
+      transform.field("position"),
+      Some(Value::List(vec![Value::Float(1.), Value::Float(2.)]))
+    );
+
+    transform.set_field("rotation", &Value::Float(90.)).unwrap();
+    assert_eq!(transform.rotation, 90.);
+
+    assert_eq!(
+      transform.set_field("scale", &Value::Float(2.)),
+      Err(ReflectError::UnknownField("scale".to_string()))
+    );
+    assert_eq!(
+      transform.set_field("rotation", &Value::Text("up".to_string())),
+      Err(ReflectError::InvalidValue("rotation".to_string()))
+    );
+  }
+
+  #[test]
+  fn describes_components() {
+    let mut tag = Tag::new(TagType::Ship);
+    tag
+      .set_field(
+        "tags",
+        &Value::List(vec![
+          Value::Text("Ship".to_string()),
+          Value::Text("Shape(2)".to_string()),
+        ]),
+      )
+      .unwrap();
+
+    assert!(tag.has(&TagType::Shape(2)));
+    assert_eq!(
+      describe("tag", &tag),
+      "tag { tags: [\"Ship\", \"Shape(2)\"] }"
+    );
+  }
+
+  #[test]
+  fn builds_registered_components_from_fields() {
+    let registry = ReflectRegistry::new().register::<Physicsable>();
+    let mut world = World::new();
+    let entity = world.create_entity();
+
+    let mut fields: FieldMap = Physicsable::new(1., 2.)
+      .fields()
+      .into_iter()
+      .map(|(name, value)| (name.to_string(), value))
+      .collect();
+
+    registry
+      .insert(&mut world, &entity, "Physicsable", &fields)
+      .unwrap();
+    assert_eq!(
+      world.get::<Physicsable>(&entity).unwrap().velocity,
+      Vector2::new(1., 2.)
+    );
+
+    fields.remove("acceleration");
+    assert_eq!(
+      registry.insert(&mut world, &entity, "Physicsable", &fields),
+      Err(ReflectError::MissingField("acceleration".to_string()))
+    );
+    assert_eq!(
+      registry.insert(&mut world, &entity, "Transform", &fields),
+      Err(ReflectError::UnknownComponent("Transform".to_string()))
+    );
+  }
+}
@@ -0,0 +1,254 @@
+use super::{FieldValue, Value};
+use crate::components::{DamageType, DrawMode, TagType};
+use crate::entity::EntityId;
+use geo::{LineString, Polygon};
+use ggez::nalgebra::{Point2, Vector2};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+impl FieldValue for bool {
+  fn to_value(&self) -> Value {
+    Value::Bool(*self)
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::Bool(b) => Some(*b),
+      _ => None,
+    }
+  }
+}
+
+macro_rules! impl_int_value {
+  ($($ty:ty),+) => {
+    $(
+      impl FieldValue for $ty {
+        fn to_value(&self) -> Value {
+          Value::Int(*self as i64)
+        }
+
+        fn from_value(value: &Value) -> Option<Self> {
+          match value {
+            Value::Int(i) => <$ty>::try_from(*i).ok(),
+            _ => None,
+          }
+        }
+      }
+    )+
+  };
+}
+
+impl_int_value!(u8, u32, i32);
+
+impl FieldValue for f32 {
+  fn to_value(&self) -> Value {
+    Value::Float(*self)
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::Float(x) => Some(*x),
+      Value::Int(i) => Some(*i as f32),
+      _ => None,
+    }
+  }
+}
+
+impl FieldValue for String {
+  fn to_value(&self) -> Value {
+    Value::Text(self.clone())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::Text(text) => Some(text.clone()),
+      _ => None,
+    }
+  }
+}
+
+impl FieldValue for EntityId {
+  fn to_value(&self) -> Value {
+    Value::Entity(*self)
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::Entity(entity) => Some(*entity),
+      _ => None,
+    }
+  }
+}
+
+impl<T: FieldValue> FieldValue for Vec<T> {
+  fn to_value(&self) -> Value {
+    Value::List(self.iter().map(T::to_value).collect())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::List(values) => values.iter().map(T::from_value).collect(),
+      _ => None,
+    }
+  }
+}
+
+/// Seconds, as a float.
+impl FieldValue for Duration {
+  fn to_value(&self) -> Value {
+    Value::Float(self.as_secs_f32())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    f32::from_value(value)
+      .filter(|secs| *secs >= 0.)
+      .map(Duration::from_secs_f32)
+  }
+}
+
+/// An empty list for `None`, or a list of one.
+impl<T: FieldValue> FieldValue for Option<T> {
+  fn to_value(&self) -> Value {
+    Value::List(self.iter().map(FieldValue::to_value).collect())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::List(values) if values.is_empty() => Some(None),
+      Value::List(values) if values.len() == 1 => T::from_value(&values[0]).map(Some),
+      _ => None,
+    }
+  }
+}
+
+fn pair(value: &Value) -> Option<(f32, f32)> {
+  match value {
+    Value::List(values) if values.len() == 2 => {
+      Some((f32::from_value(&values[0])?, f32::from_value(&values[1])?))
+    }
+    _ => None,
+  }
+}
+
+/// Colors, as a list of red, green, blue and alpha.
+impl FieldValue for (f32, f32, f32, f32) {
+  fn to_value(&self) -> Value {
+    let (r, g, b, a) = *self;
+    Value::List(vec![
+      Value::Float(r),
+      Value::Float(g),
+      Value::Float(b),
+      Value::Float(a),
+    ])
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match value {
+      Value::List(values) if values.len() == 4 => Some((
+        f32::from_value(&values[0])?,
+        f32::from_value(&values[1])?,
+        f32::from_value(&values[2])?,
+        f32::from_value(&values[3])?,
+      )),
+      _ => None,
+    }
+  }
+}
+
+impl FieldValue for Point2<f32> {
+  fn to_value(&self) -> Value {
+    Value::List(vec![Value::Float(self.x), Value::Float(self.y)])
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    pair(value).map(|(x, y)| Point2::new(x, y))
+  }
+}
+
+impl FieldValue for Vector2<f32> {
+  fn to_value(&self) -> Value {
+    Value::List(vec![Value::Float(self.x), Value::Float(self.y)])
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    pair(value).map(|(x, y)| Vector2::new(x, y))
+  }
+}
+
+/// The exterior ring, as a list of points.
+impl FieldValue for Polygon<f32> {
+  fn to_value(&self) -> Value {
+    Value::List(
+      self
+        .exterior()
+        .points_iter()
+        .map(|p| Point2::new(p.x(), p.y()).to_value())
+        .collect(),
+    )
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    let points: Vec<Point2<f32>> = Vec::from_value(value)?;
+    let line: Vec<(f32, f32)> = points.iter().map(|p| (p.x, p.y)).collect();
+
+    Some(Polygon::new(LineString::from(line), vec![]))
+  }
+}
+
+impl FieldValue for TagType {
+  fn to_value(&self) -> Value {
+    Value::Text(self.to_string())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    let text = String::from_value(value)?;
+
+    match text.as_str() {
+      "Ship" => Some(TagType::Ship),
+      "Bullet" => Some(TagType::Bullet),
+      _ => text
+        .strip_prefix("Shape(")
+        .and_then(|level| level.strip_suffix(')'))
+        .and_then(|level| level.parse().ok())
+        .map(TagType::Shape),
+    }
+  }
+}
+
+impl FieldValue for DamageType {
+  fn to_value(&self) -> Value {
+    let name = match self {
+      DamageType::Projectile => "Projectile",
+      DamageType::Smash => "Smash",
+    };
+
+    Value::Text(name.to_string())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match String::from_value(value)?.as_str() {
+      "Projectile" => Some(DamageType::Projectile),
+      "Smash" => Some(DamageType::Smash),
+      _ => None,
+    }
+  }
+}
+
+impl FieldValue for DrawMode {
+  fn to_value(&self) -> Value {
+    let name = match self {
+      DrawMode::Stroke => "Stroke",
+      DrawMode::Fill => "Fill",
+    };
+
+    Value::Text(name.to_string())
+  }
+
+  fn from_value(value: &Value) -> Option<Self> {
+    match String::from_value(value)?.as_str() {
+      "Stroke" => Some(DrawMode::Stroke),
+      "Fill" => Some(DrawMode::Fill),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use ggez::GameError;
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+
+mod arena_size;
+mod fire_cooldown;
+mod fixed_time;
+mod frame_time;
+mod game_clock;
+mod input_state;
+mod scoreboard;
+mod time_scale;
+
+pub use arena_size::ArenaSize;
+pub use fire_cooldown::FireCooldown;
+pub use fixed_time::FixedTime;
+pub use frame_time::FrameTime;
+pub use game_clock::GameClock;
+pub use input_state::InputState;
+pub use scoreboard::Scoreboard;
+pub use time_scale::TimeScale;
+
+/// Global singleton state stored on the `World` rather than on an entity.
+pub trait Resource: Any + Send + Sync + 'static {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceError {
+  pub name: &'static str,
+}
+
+impl ResourceError {
+  pub fn missing<R: Resource>() -> Self {
+    ResourceError {
+      name: std::any::type_name::<R>(),
+    }
+  }
+}
+
+impl fmt::Display for ResourceError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "resource {} has not been inserted into the world",
+      self.name
+    )
+  }
+}
+
+impl Error for ResourceError {}
+
+impl From<ResourceError> for GameError {
+  fn from(error: ResourceError) -> Self {
+    GameError::ResourceLoadError(error.to_string())
+  }
+}
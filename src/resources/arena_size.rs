@@ -0,0 +1,15 @@
+use super::Resource;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArenaSize {
+  pub width: f32,
+  pub height: f32,
+}
+
+impl ArenaSize {
+  pub fn new(width: f32, height: f32) -> Self {
+    ArenaSize { width, height }
+  }
+}
+
+impl Resource for ArenaSize {}
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use super::Resource;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FireCooldown {
+  pub duration: Duration,
+}
+
+impl FireCooldown {
+  pub fn new(duration: Duration) -> Self {
+    FireCooldown { duration }
+  }
+}
+
+impl Resource for FireCooldown {}
+
+impl Default for FireCooldown {
+  fn default() -> Self {
+    FireCooldown::new(Duration::from_secs(2))
+  }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use super::Resource;
+
+/// Runs the simulation in fixed steps however fast frames come. Real time is
+/// banked each frame and spent a whole `step` at a time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedTime {
+  pub step: Duration,
+  /// Most steps run in one frame. Time beyond that is dropped so a long
+  /// stall doesn't snowball into ever longer frames.
+  pub max_steps: u32,
+  accumulator: Duration,
+}
+
+impl FixedTime {
+  pub fn new(step: Duration, max_steps: u32) -> Self {
+    FixedTime {
+      step,
+      max_steps,
+      accumulator: Duration::from_secs(0),
+    }
+  }
+
+  pub fn from_hz(hz: u32, max_steps: u32) -> Self {
+    FixedTime::new(Duration::from_secs(1) / hz, max_steps)
+  }
+
+  /// Seconds simulated by each step.
+  pub fn delta(&self) -> f32 {
+    self.step.as_secs_f32()
+  }
+
+  /// Banks `elapsed` and returns how many steps to run this frame.
+  pub fn accumulate(&mut self, elapsed: Duration) -> u32 {
+    self.accumulator += elapsed;
+
+    let mut steps = 0;
+    while self.accumulator >= self.step {
+      if steps == self.max_steps {
+        self.accumulator =
+          Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64);
+        break;
+      }
+
+      self.accumulator -= self.step;
+      steps += 1;
+    }
+
+    steps
+  }
+
+  /// How far between the last step and the next one the current frame is,
+  /// from 0 to 1, for drawing between steps.
+  pub fn alpha(&self) -> f32 {
+    self.accumulator.as_secs_f32() / self.delta()
+  }
+}
+
+impl Resource for FixedTime {}
+
+impl Default for FixedTime {
+  fn default() -> Self {
+    FixedTime::from_hz(60, 5)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn runs_whole_steps_and_banks_the_rest() {
+    let mut time = FixedTime::new(Duration::from_millis(10), 3);
+
+    assert_eq!(time.accumulate(Duration::from_millis(4)), 0);
+    assert_eq!(time.accumulate(Duration::from_millis(21)), 2);
+    assert!((time.alpha() - 0.5).abs() < 0.001);
+
+    assert_eq!(time.accumulate(Duration::from_millis(102)), 3);
+    assert!((time.alpha() - 0.7).abs() < 0.001);
+  }
+}
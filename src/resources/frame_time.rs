@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use super::Resource;
+
+/// Real time since the last frame, unaffected by `TimeScale`. Read by the
+/// `Input` stage, which runs once per frame rather than once per fixed step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTime {
+  pub elapsed: Duration,
+}
+
+impl FrameTime {
+  pub fn new(elapsed: Duration) -> Self {
+    FrameTime { elapsed }
+  }
+
+  /// Real seconds since the last frame.
+  pub fn delta(&self) -> f32 {
+    self.elapsed.as_secs_f32()
+  }
+}
+
+impl Resource for FrameTime {}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use super::Resource;
+use serde::{Deserialize, Serialize};
+
+/// Simulated time. Advanced once per fixed step, so it stands still while
+/// the simulation isn't running and is saved along with the game. Timestamps
+/// on components are `elapsed` values from this clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameClock {
+  tick: u64,
+  elapsed: Duration,
+  delta: Duration,
+}
+
+impl GameClock {
+  pub fn new() -> Self {
+    GameClock::default()
+  }
+
+  /// Moves the clock on by one tick of `delta`. Tests drive the clock this
+  /// way rather than waiting on real time.
+  pub fn advance(&mut self, delta: Duration) {
+    self.tick += 1;
+    self.elapsed += delta;
+    self.delta = delta;
+  }
+
+  /// Ticks run since the game started.
+  #[allow(dead_code, reason = "only read by tests so far")]
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  /// Simulated time since the game started.
+  pub fn elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  /// Length of the last tick.
+  #[allow(dead_code, reason = "systems read the step length from `FixedTime`")]
+  pub fn delta(&self) -> Duration {
+    self.delta
+  }
+
+  /// Simulated time since `timestamp`.
+  pub fn since(&self, timestamp: Duration) -> Duration {
+    self.elapsed.checked_sub(timestamp).unwrap_or_default()
+  }
+}
+
+impl Resource for GameClock {}
@@ -0,0 +1,32 @@
+use ggez::event::KeyCode;
+use std::collections::HashSet;
+
+use super::Resource;
+
+/// Keys currently held down. Kept up to date from window events, so systems
+/// can read the keyboard without the ggez context and tests can press keys
+/// themselves.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputState {
+  pressed: HashSet<KeyCode>,
+}
+
+impl InputState {
+  pub fn new() -> Self {
+    InputState::default()
+  }
+
+  pub fn press(&mut self, key: KeyCode) {
+    self.pressed.insert(key);
+  }
+
+  pub fn release(&mut self, key: KeyCode) {
+    self.pressed.remove(&key);
+  }
+
+  pub fn is_pressed(&self, key: KeyCode) -> bool {
+    self.pressed.contains(&key)
+  }
+}
+
+impl Resource for InputState {}
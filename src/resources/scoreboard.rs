@@ -0,0 +1,27 @@
+use super::Resource;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scoreboard {
+  pub score: u32,
+  pub lives: u32,
+  pub wave: u32,
+}
+
+impl Scoreboard {
+  pub fn new(lives: u32) -> Self {
+    Scoreboard {
+      score: 0,
+      lives,
+      wave: 1,
+    }
+  }
+}
+
+impl Resource for Scoreboard {}
+
+impl Default for Scoreboard {
+  fn default() -> Self {
+    Scoreboard::new(3)
+  }
+}
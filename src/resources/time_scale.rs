@@ -0,0 +1,94 @@
+use std::mem;
+use std::time::Duration;
+
+use super::Resource;
+
+/// How fast the simulation runs against real time. Scaling happens before
+/// real time is banked in `FixedTime`, so every simulation system slows down
+/// together, while the `Input` stage and drawing still run every frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeScale {
+  /// Simulated seconds per real second. 1 is full speed, 0.25 bullet time.
+  pub scale: f32,
+  paused: bool,
+  frame_step: bool,
+  queued_steps: u32,
+}
+
+impl TimeScale {
+  pub fn new(scale: f32) -> Self {
+    TimeScale {
+      scale,
+      paused: false,
+      frame_step: false,
+      queued_steps: 0,
+    }
+  }
+
+  pub fn toggle_pause(&mut self) {
+    self.paused = !self.paused;
+  }
+
+  pub fn toggle_frame_step(&mut self) {
+    self.frame_step = !self.frame_step;
+    self.queued_steps = 0;
+  }
+
+  /// Queues exactly one tick while frame stepping. Does nothing otherwise.
+  pub fn step(&mut self) {
+    if self.frame_step {
+      self.queued_steps += 1;
+    }
+  }
+
+  /// Real `elapsed` time as simulated time. Nothing passes while paused or
+  /// frame stepping.
+  pub fn apply(&self, elapsed: Duration) -> Duration {
+    if self.paused || self.frame_step {
+      return Duration::from_secs(0);
+    }
+
+    elapsed.mul_f32(self.scale.max(0.0))
+  }
+
+  /// Ticks queued by `step` since the last call.
+  pub fn take_steps(&mut self) -> u32 {
+    mem::take(&mut self.queued_steps)
+  }
+}
+
+impl Resource for TimeScale {}
+
+impl Default for TimeScale {
+  fn default() -> Self {
+    TimeScale::new(1.0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn scales_pauses_and_steps_simulated_time() {
+    let frame = Duration::from_secs(1);
+    let mut time = TimeScale::new(0.5);
+    assert_eq!(time.apply(frame), Duration::from_millis(500));
+
+    time.toggle_pause();
+    assert_eq!(time.apply(frame), Duration::from_secs(0));
+    time.step();
+    assert_eq!(time.take_steps(), 0);
+
+    time.toggle_pause();
+    time.toggle_frame_step();
+    assert_eq!(time.apply(frame), Duration::from_secs(0));
+    time.step();
+    time.step();
+    assert_eq!(time.take_steps(), 2);
+    assert_eq!(time.take_steps(), 0);
+
+    time.toggle_frame_step();
+    assert_eq!(time.apply(frame), Duration::from_millis(500));
+  }
+}
@@ -0,0 +1,461 @@
+use crate::components::{
+  Children, Collidable, Component, Damage, Drawable, Expirable, GlobalTransform, Parent,
+  Physicsable, PlayerControllable, PrefabName, Tag, Transform, Vulnerable,
+};
+use crate::entity::EntityId;
+use crate::events::Event;
+use crate::prefab::Prefabs;
+use crate::reflect::{FieldMap, ReflectError, ReflectRegistry, Value};
+use crate::resources::{GameClock, Scoreboard};
+use crate::world::World;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A saved game. Entities keep the ids they had when saved so references
+/// between them can be remapped on load.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveFile {
+  pub scoreboard: Scoreboard,
+  /// Timestamps like `Expirable::created` are relative to this clock.
+  #[serde(default)]
+  pub clock: GameClock,
+  pub entities: Vec<SavedEntity>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedEntity {
+  pub id: EntityId,
+  pub components: BTreeMap<String, FieldMap>,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+  Io(io::Error),
+  Format(String),
+  Component(String, ReflectError),
+  DuplicateEntity(EntityId),
+}
+
+impl fmt::Display for SaveError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SaveError::Io(error) => write!(f, "could not access save file: {}", error),
+      SaveError::Format(error) => write!(f, "invalid save file: {}", error),
+      SaveError::Component(name, error) => write!(f, "could not load {}: {}", name, error),
+      SaveError::DuplicateEntity(entity) => write!(f, "entity {} is saved twice", entity),
+    }
+  }
+}
+
+impl Error for SaveError {}
+
+impl Event for SaveError {}
+
+impl From<io::Error> for SaveError {
+  fn from(error: io::Error) -> Self {
+    SaveError::Io(error)
+  }
+}
+
+/// Components a save file can contain.
+pub fn registry() -> ReflectRegistry {
+  ReflectRegistry::new()
+    .register::<Children>()
+    .register::<Damage>()
+    .register::<Drawable>()
+    .register::<Expirable>()
+    .register::<GlobalTransform>()
+    .register::<Parent>()
+    .register::<Physicsable>()
+    .register::<PlayerControllable>()
+    .register::<PrefabName>()
+    .register::<Tag>()
+    .register::<Transform>()
+    .register::<Vulnerable>()
+}
+
+fn is_rebuilt(name: &str) -> bool {
+  name == Collidable::name()
+}
+
+pub fn snapshot(world: &World) -> SaveFile {
+  let mut entities: Vec<EntityId> = world.live_entities().collect();
+  entities.sort();
+
+  let entities = entities
+    .into_iter()
+    .map(|entity| {
+      let components = world
+        .components_of(&entity)
+        .into_iter()
+        .filter(|(name, _)| !is_rebuilt(name))
+        .filter_map(|(name, component)| {
+          let fields = component
+            .as_reflect()?
+            .fields()
+            .into_iter()
+            .map(|(field, value)| (field.to_string(), value))
+            .collect();
+
+          Some((name.to_string(), fields))
+        })
+        .collect();
+
+      SavedEntity {
+        id: entity,
+        components,
+      }
+    })
+    .collect();
+
+  SaveFile {
+    scoreboard: world.resource::<Scoreboard>().cloned().unwrap_or_default(),
+    clock: world.resource::<GameClock>().cloned().unwrap_or_default(),
+    entities,
+  }
+}
+
+pub fn to_string(file: &SaveFile) -> Result<String, SaveError> {
+  ron::ser::to_string_pretty(file, PrettyConfig::default())
+    .map_err(|error| SaveError::Format(error.to_string()))
+}
+
+pub fn from_str(text: &str) -> Result<SaveFile, SaveError> {
+  ron::from_str(text).map_err(|error| SaveError::Format(error.to_string()))
+}
+
+/// Replaces every entity in `world` with the saved ones. `Collidable` isn't
+/// saved, so it's rebuilt from each entity's prefab. Every component is
+/// checked before anything is replaced, so a bad save leaves `world` as it
+/// was.
+///
+/// References to entities that weren't saved are handed to each component's
+/// `OnDespawn`, as if their target had been despawned while loading.
+pub fn restore(
+  world: &mut World,
+  file: &SaveFile,
+  registry: &ReflectRegistry,
+) -> Result<Vec<EntityId>, SaveError> {
+  let mut saved_ids = HashSet::new();
+  for saved in &file.entities {
+    if !saved_ids.insert(saved.id) {
+      return Err(SaveError::DuplicateEntity(saved.id));
+    }
+    for (name, fields) in &saved.components {
+      registry
+        .validate(name, fields)
+        .map_err(|error| SaveError::Component(name.clone(), error))?;
+    }
+  }
+
+  let existing: Vec<EntityId> = world.live_entities().collect();
+  world.remove_all(existing);
+  world.insert_resource(file.scoreboard.clone());
+  world.insert_resource(file.clock);
+
+  let mut ids: HashMap<EntityId, EntityId> = file
+    .entities
+    .iter()
+    .map(|saved| (saved.id, world.create_entity()))
+    .collect();
+
+  let mut unsaved = vec![];
+  for saved in &file.entities {
+    for value in saved.components.values().flat_map(|fields| fields.values()) {
+      collect_refs(value, &mut unsaved);
+    }
+  }
+  unsaved.retain(|entity| !ids.contains_key(entity));
+  unsaved.sort();
+  unsaved.dedup();
+  let placeholders: Vec<EntityId> = unsaved
+    .into_iter()
+    .map(|entity| {
+      let placeholder = world.create_entity();
+      ids.insert(entity, placeholder);
+      placeholder
+    })
+    .collect();
+
+  for saved in &file.entities {
+    let entity = ids[&saved.id];
+
+    for (name, fields) in &saved.components {
+      let fields = fields
+        .iter()
+        .map(|(field, value)| (field.clone(), remap(value, &ids)))
+        .collect();
+
+      registry
+        .insert(world, &entity, name, &fields)
+        .map_err(|error| SaveError::Component(name.clone(), error))?;
+    }
+  }
+
+  for placeholder in &placeholders {
+    world.remove(placeholder);
+  }
+
+  let entities: Vec<EntityId> = file.entities.iter().map(|saved| ids[&saved.id]).collect();
+  for entity in &entities {
+    rebuild(world, entity);
+  }
+
+  Ok(entities)
+}
+
+fn rebuild(world: &mut World, entity: &EntityId) {
+  let collidable = world.get::<PrefabName>(entity).and_then(|name| {
+    let prefabs = world.resource::<Prefabs>().ok()?;
+    Some(prefabs.get(&name.name).ok()?.collidable())
+  });
+
+  if let Some(collidable) = collidable {
+    world.add(entity, collidable);
+  }
+}
+
+fn collect_refs(value: &Value, refs: &mut Vec<EntityId>) {
+  match value {
+    Value::Entity(entity) => refs.push(*entity),
+    Value::List(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+    _ => {}
+  }
+}
+
+/// Points saved references at the loaded entities, or at the placeholders
+/// standing in for entities that weren't saved.
+fn remap(value: &Value, ids: &HashMap<EntityId, EntityId>) -> Value {
+  match value {
+    Value::Entity(entity) => Value::Entity(ids[entity]),
+    Value::List(values) => Value::List(values.iter().map(|v| remap(v, ids)).collect()),
+    value => value.clone(),
+  }
+}
+
+pub fn save<P: AsRef<Path>>(world: &World, path: P) -> Result<(), SaveError> {
+  let text = to_string(&snapshot(world))?;
+  fs::write(path, text)?;
+
+  Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(world: &mut World, path: P) -> Result<(), SaveError> {
+  let text = fs::read_to_string(path)?;
+  restore(world, &from_str(&text)?, &registry())?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::components::{DrawMode, TagType};
+  use ggez::nalgebra::Point2;
+  use std::time::Duration;
+
+  #[test]
+  fn restores_a_saved_world() {
+    let mut world = World::new();
+    world.insert_resource(Scoreboard {
+      score: 300,
+      lives: 2,
+      wave: 4,
+    });
+
+    let bullet = world.create_entity();
+    world.add(&bullet, Tag::new(TagType::Bullet));
+    world.add(&bullet, PrefabName::new("bullet"));
+    world.add(&bullet, Transform::new(10., 20.));
+    let drawable = Drawable::new(
+      vec![
+        Point2::new(0., 0.),
+        Point2::new(2., 0.),
+        Point2::new(2., 2.),
+      ],
+      DrawMode::Fill,
+      (1., 0.5, 0., 1.),
+      2.,
+      Point2::new(1., 1.),
+    );
+    world.add(&bullet, drawable.clone());
+    let mut clock = GameClock::new();
+    clock.advance(Duration::from_secs(2));
+    world.insert_resource(clock);
+    world.add(
+      &bullet,
+      Expirable::new(Duration::from_secs(1), Duration::from_secs(3)),
+    );
+
+    let ship = world.create_entity();
+    world.add(&ship, Tag::new(TagType::Ship).with(TagType::Shape(4)));
+    world.add(&ship, Transform::new(1., 2.));
+    world.set_parent(&bullet, &ship);
+
+    let text = to_string(&snapshot(&world)).unwrap();
+    let file = from_str(&text).unwrap();
+
+    let mut loaded = World::new();
+    let mut prefabs = Prefabs::new();
+    prefabs
+      .parse(
+        "bullet",
+        "(tags: [Bullet], shape: Polygon(sides: 4, length: 1.0, rotation: 0.0))",
+      )
+      .unwrap();
+    loaded.insert_resource(prefabs);
+    let stale = loaded.create_entity();
+    let entities = restore(&mut loaded, &file, &registry()).unwrap();
+
+    assert!(!loaded.is_alive(&stale));
+    assert_eq!(entities.len(), 2);
+    assert_eq!(loaded.resource::<Scoreboard>().unwrap().score, 300);
+    assert_eq!(*loaded.resource::<GameClock>().unwrap(), clock);
+
+    let tagged = |tag_type: TagType| {
+      *entities
+        .iter()
+        .find(|entity| loaded.get::<Tag>(entity).unwrap().has(&tag_type))
+        .unwrap()
+    };
+    let bullet = &tagged(TagType::Bullet);
+    let ship = &tagged(TagType::Ship);
+
+    assert_eq!(loaded.tagged(TagType::Shape(4)), vec![*ship]);
+    assert_eq!(loaded.parent(bullet), Some(*ship));
+    assert_eq!(loaded.children(ship), vec![*bullet]);
+    assert!(loaded.has::<Collidable>(bullet));
+    assert_eq!(loaded.get::<Drawable>(bullet), Some(&drawable));
+    assert!(!loaded.has::<Collidable>(ship));
+    assert_eq!(
+      loaded.get::<Transform>(bullet).unwrap().position,
+      Transform::new(10., 20.).position
+    );
+
+    let expirable = loaded.get::<Expirable>(bullet).unwrap();
+    assert_eq!(expirable.expiration, Duration::from_secs(3));
+    assert_eq!(expirable.created, Duration::from_secs(1));
+  }
+
+  fn saved(index: u32, components: Vec<(&str, &str, Value)>) -> SavedEntity {
+    SavedEntity {
+      id: EntityId::new(index, 0),
+      components: components
+        .into_iter()
+        .map(|(component, field, value)| {
+          let fields = vec![(field.to_string(), value)].into_iter().collect();
+          (component.to_string(), fields)
+        })
+        .collect(),
+    }
+  }
+
+  #[test]
+  fn applies_on_despawn_to_references_to_unsaved_entities() {
+    let unsaved = Value::Entity(EntityId::new(7, 0));
+    let file = SaveFile {
+      scoreboard: Scoreboard::default(),
+      clock: GameClock::new(),
+      entities: vec![
+        saved(0, vec![("Parent", "entity", unsaved.clone())]),
+        saved(
+          1,
+          vec![(
+            "Children",
+            "entities",
+            Value::List(vec![unsaved, Value::Entity(EntityId::new(2, 0))]),
+          )],
+        ),
+        saved(
+          2,
+          vec![("Parent", "entity", Value::Entity(EntityId::new(1, 0)))],
+        ),
+      ],
+    };
+
+    let mut world = World::new();
+    for _ in 0..8 {
+      world.create_entity();
+    }
+    let entities = restore(&mut world, &file, &registry()).unwrap();
+
+    assert_eq!(world.live_entities().count(), 3);
+    assert!(!world.has::<Parent>(&entities[0]));
+    assert_eq!(world.children(&entities[1]), vec![entities[2]]);
+    assert_eq!(world.parent(&entities[2]), Some(entities[1]));
+    assert!(world.dangling_references().is_empty());
+  }
+
+  #[test]
+  fn rejects_duplicate_entity_ids() {
+    let file = SaveFile {
+      scoreboard: Scoreboard::default(),
+      clock: GameClock::new(),
+      entities: vec![
+        saved(
+          0,
+          vec![("PrefabName", "name", Value::Text("ship".to_string()))],
+        ),
+        saved(
+          0,
+          vec![("PrefabName", "name", Value::Text("bullet".to_string()))],
+        ),
+      ],
+    };
+
+    let mut world = World::new();
+    let ship = world.create_entity();
+    let error = restore(&mut world, &file, &registry()).unwrap_err();
+
+    assert_eq!(error.to_string(), "entity 0v0 is saved twice");
+    assert_eq!(world.live_entities().collect::<Vec<_>>(), vec![ship]);
+  }
+
+  #[test]
+  fn rejects_unknown_components() {
+    let mut world = World::new();
+    world.insert_resource(Scoreboard {
+      score: 300,
+      lives: 2,
+      wave: 4,
+    });
+    let ship = world.create_entity();
+    world.add(&ship, Transform::new(1., 2.));
+
+    let save = |components: &str| {
+      format!(
+        r#"(
+          scoreboard: (score: 0, lives: 3, wave: 1),
+          entities: [
+            (id: (index: 0, generation: 0), components: {{ "PrefabName": {{ "name": "ship" }} }}),
+            (id: (index: 1, generation: 0), components: {{ {} }}),
+          ],
+        )"#,
+        components
+      )
+    };
+
+    let unknown = from_str(&save(r#""Shield": { "strength": 3 }"#)).unwrap();
+    let error = restore(&mut world, &unknown, &registry()).unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "could not load Shield: no component named Shield"
+    );
+
+    let malformed = from_str(&save(r#""PrefabName": { "name": 3 }"#)).unwrap();
+    assert!(restore(&mut world, &malformed, &registry()).is_err());
+
+    assert_eq!(world.live_entities().collect::<Vec<_>>(), vec![ship]);
+    assert_eq!(
+      world.get::<Transform>(&ship).unwrap().position,
+      Transform::new(1., 2.).position
+    );
+    assert_eq!(world.resource::<Scoreboard>().unwrap().score, 300);
+  }
+}
@@ -0,0 +1,566 @@
+use crate::commands::Commands;
+use crate::systems::System;
+use crate::world::World;
+use ggez::{Context, GameError, GameResult};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Coarse phases of a frame. Every system in a stage runs after every system
+/// in the stages before it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Stage {
+  Input,
+  Simulation,
+  PostSimulation,
+  Render,
+}
+
+impl Stage {
+  pub fn all() -> [Stage; 4] {
+    [
+      Stage::Input,
+      Stage::Simulation,
+      Stage::PostSimulation,
+      Stage::Render,
+    ]
+  }
+}
+
+/// The component, resource and event types a system reads and writes. Systems
+/// whose access doesn't overlap share a batch.
+#[derive(Clone, Debug, Default)]
+pub struct SystemAccess {
+  reads: Vec<TypeId>,
+  writes: Vec<TypeId>,
+  exclusive: bool,
+  context: bool,
+}
+
+impl SystemAccess {
+  pub fn new() -> Self {
+    SystemAccess::default()
+  }
+
+  /// Access for systems that may touch anything in the world.
+  pub fn exclusive() -> Self {
+    SystemAccess {
+      exclusive: true,
+      ..SystemAccess::default()
+    }
+  }
+
+  pub fn reads<T: 'static>(mut self) -> Self {
+    self.reads.push(TypeId::of::<T>());
+    self
+  }
+
+  pub fn writes<T: 'static>(mut self) -> Self {
+    self.writes.push(TypeId::of::<T>());
+    self
+  }
+
+  /// Marks the system as needing the ggez context, so headless runs refuse
+  /// it.
+  pub fn context(mut self) -> Self {
+    self.context = true;
+    self
+  }
+
+  pub fn uses_context(&self) -> bool {
+    self.context
+  }
+
+  pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+    if self.exclusive || other.exclusive {
+      return true;
+    }
+
+    let writes_read = |a: &SystemAccess, b: &SystemAccess| {
+      a.writes
+        .iter()
+        .any(|t| b.writes.contains(t) || b.reads.contains(t))
+    };
+
+    writes_read(self, other) || writes_read(other, self)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+  DuplicateLabel(&'static str),
+  UnknownLabel(&'static str, &'static str),
+  StageConflict(&'static str, &'static str),
+  Cycle(Vec<&'static str>),
+}
+
+impl fmt::Display for ScheduleError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ScheduleError::DuplicateLabel(label) => {
+        write!(f, "more than one system is labelled {}", label)
+      }
+      ScheduleError::UnknownLabel(system, label) => {
+        write!(
+          f,
+          "system {} is ordered against unknown label {}",
+          system, label
+        )
+      }
+      ScheduleError::StageConflict(system, label) => write!(
+        f,
+        "system {} is ordered against {} in a way its stage can't satisfy",
+        system, label
+      ),
+      ScheduleError::Cycle(labels) => {
+        write!(f, "systems have cyclic ordering: {}", labels.join(", "))
+      }
+    }
+  }
+}
+
+impl Error for ScheduleError {}
+
+impl From<ScheduleError> for GameError {
+  fn from(error: ScheduleError) -> Self {
+    GameError::ConfigError(error.to_string())
+  }
+}
+
+/// Collects systems before their ordering constraints are resolved.
+pub struct Scheduler {
+  systems: Vec<Box<dyn System>>,
+}
+
+impl Scheduler {
+  pub fn new() -> Self {
+    Scheduler { systems: vec![] }
+  }
+
+  pub fn with<S: System + 'static>(mut self, system: S) -> Self {
+    self.systems.push(Box::new(system));
+    self
+  }
+
+  /// Validates the declared labels and constraints and resolves them into a
+  /// run order for each stage. Systems without constraints between them keep
+  /// the order they were added in.
+  pub fn build(self) -> Result<Schedule, ScheduleError> {
+    let mut labels: HashMap<&'static str, usize> = HashMap::new();
+    for (i, system) in self.systems.iter().enumerate() {
+      if labels.insert(system.label(), i).is_some() {
+        return Err(ScheduleError::DuplicateLabel(system.label()));
+      }
+    }
+
+    // edges[a] contains b when a must run before b
+    let mut edges: Vec<Vec<usize>> = vec![vec![]; self.systems.len()];
+    for (i, system) in self.systems.iter().enumerate() {
+      let constraints = system
+        .before()
+        .into_iter()
+        .map(|label| (label, true))
+        .chain(system.after().into_iter().map(|label| (label, false)));
+
+      for (label, before) in constraints {
+        let other = *labels
+          .get(label)
+          .ok_or(ScheduleError::UnknownLabel(system.label(), label))?;
+        let (first, second) = if before { (i, other) } else { (other, i) };
+
+        let (first_stage, second_stage) =
+          (self.systems[first].stage(), self.systems[second].stage());
+        if first_stage > second_stage {
+          return Err(ScheduleError::StageConflict(system.label(), label));
+        }
+        if first_stage == second_stage {
+          edges[first].push(second);
+        }
+      }
+    }
+
+    let access: Vec<SystemAccess> = self.systems.iter().map(|s| s.access()).collect();
+
+    let mut stages = vec![];
+    for stage in Stage::all().iter() {
+      let members: Vec<usize> = (0..self.systems.len())
+        .filter(|i| self.systems[*i].stage() == *stage)
+        .collect();
+
+      let order = sort(&self.systems, &members, &edges)?;
+      stages.push((*stage, batch(&order, &access, &edges)));
+    }
+
+    Ok(Schedule {
+      systems: self.systems,
+      access,
+      stages,
+    })
+  }
+}
+
+impl Default for Scheduler {
+  fn default() -> Self {
+    Scheduler::new()
+  }
+}
+
+/// Kahn's algorithm, always picking the earliest added system that is ready so
+/// the result is deterministic.
+fn sort(
+  systems: &[Box<dyn System>],
+  members: &[usize],
+  edges: &[Vec<usize>],
+) -> Result<Vec<usize>, ScheduleError> {
+  let mut incoming: HashMap<usize, usize> = members.iter().map(|i| (*i, 0)).collect();
+  for member in members {
+    for next in &edges[*member] {
+      *incoming.get_mut(next).unwrap() += 1;
+    }
+  }
+
+  let mut sorted = vec![];
+  while sorted.len() < members.len() {
+    let ready = members
+      .iter()
+      .find(|i| incoming.get(i) == Some(&0) && !sorted.contains(*i));
+
+    let ready = match ready {
+      Some(ready) => *ready,
+      None => {
+        let cycle = members
+          .iter()
+          .filter(|i| !sorted.contains(*i))
+          .map(|i| systems[*i].label())
+          .collect();
+        return Err(ScheduleError::Cycle(cycle));
+      }
+    };
+
+    for next in &edges[ready] {
+      *incoming.get_mut(next).unwrap() -= 1;
+    }
+    sorted.push(ready);
+  }
+
+  Ok(sorted)
+}
+
+/// Splits a stage's resolved order into consecutive batches whose systems
+/// neither conflict nor are ordered against each other.
+fn batch(order: &[usize], access: &[SystemAccess], edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+  let mut batches: Vec<Vec<usize>> = vec![];
+
+  for system in order {
+    let fits = batches.last().is_some_and(|batch| {
+      batch.iter().all(|other| {
+        !access[*system].conflicts_with(&access[*other]) && !edges[*other].contains(system)
+      })
+    });
+
+    match batches.last_mut() {
+      Some(batch) if fits => batch.push(*system),
+      _ => batches.push(vec![*system]),
+    }
+  }
+
+  batches
+}
+
+/// Systems in their resolved run order, grouped into batches whose systems
+/// neither conflict nor are ordered against each other. Batches run one
+/// system at a time on the calling thread.
+pub struct Schedule {
+  systems: Vec<Box<dyn System>>,
+  access: Vec<SystemAccess>,
+  stages: Vec<(Stage, Vec<Vec<usize>>)>,
+}
+
+impl Schedule {
+  /// Runs every batch in `stage`. The commands queued by a batch are applied,
+  /// in system order, before the next batch starts.
+  pub fn run(
+    &mut self,
+    stage: Stage,
+    world: &mut World,
+    commands: &mut Commands,
+    context: &mut Context,
+  ) -> GameResult {
+    self.run_stage(stage, world, commands, Some(context))
+  }
+
+  /// Runs `stage` without a window. Fails if a system in it needs the ggez
+  /// context.
+  pub fn run_headless(
+    &mut self,
+    stage: Stage,
+    world: &mut World,
+    commands: &mut Commands,
+  ) -> GameResult {
+    self.run_stage(stage, world, commands, None)
+  }
+
+  fn run_stage(
+    &mut self,
+    stage: Stage,
+    world: &mut World,
+    commands: &mut Commands,
+    mut context: Option<&mut Context>,
+  ) -> GameResult {
+    let batches = self
+      .stages
+      .iter()
+      .filter(|(s, _)| *s == stage)
+      .flat_map(|(_, batches)| batches.iter());
+
+    for batch in batches {
+      for i in batch {
+        run_system(
+          &mut self.systems[*i],
+          &self.access[*i],
+          world,
+          commands,
+          context.as_deref_mut(),
+        )?;
+      }
+
+      world.apply(commands);
+    }
+
+    Ok(())
+  }
+
+  #[cfg(test)]
+  pub fn labels(&self, stage: Stage) -> Vec<&'static str> {
+    self
+      .batches(stage)
+      .into_iter()
+      .flat_map(|batch| batch.into_iter())
+      .collect()
+  }
+
+  #[cfg(test)]
+  pub fn batches(&self, stage: Stage) -> Vec<Vec<&'static str>> {
+    self
+      .stages
+      .iter()
+      .filter(|(s, _)| *s == stage)
+      .flat_map(|(_, batches)| batches.iter())
+      .map(|batch| batch.iter().map(|i| self.systems[*i].label()).collect())
+      .collect()
+  }
+}
+
+fn run_system(
+  system: &mut Box<dyn System>,
+  access: &SystemAccess,
+  world: &mut World,
+  commands: &mut Commands,
+  context: Option<&mut Context>,
+) -> GameResult {
+  match context {
+    Some(context) => system.update(world, commands, context),
+    None if access.uses_context() => Err(GameError::WindowError(format!(
+      "system {} needs a ggez context",
+      system.label()
+    ))),
+    None => system.run(world, commands),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct TestSystem {
+    label: &'static str,
+    stage: Stage,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    access: SystemAccess,
+  }
+
+  impl TestSystem {
+    fn new(label: &'static str, stage: Stage) -> Self {
+      TestSystem {
+        label,
+        stage,
+        before: vec![],
+        after: vec![],
+        access: SystemAccess::exclusive(),
+      }
+    }
+
+    fn access(mut self, access: SystemAccess) -> Self {
+      self.access = access;
+      self
+    }
+
+    fn before(mut self, label: &'static str) -> Self {
+      self.before.push(label);
+      self
+    }
+
+    fn after(mut self, label: &'static str) -> Self {
+      self.after.push(label);
+      self
+    }
+  }
+
+  impl System for TestSystem {
+    fn label(&self) -> &'static str {
+      self.label
+    }
+
+    fn stage(&self) -> Stage {
+      self.stage
+    }
+
+    fn before(&self) -> Vec<&'static str> {
+      self.before.clone()
+    }
+
+    fn after(&self) -> Vec<&'static str> {
+      self.after.clone()
+    }
+
+    fn access(&self) -> SystemAccess {
+      self.access.clone()
+    }
+  }
+
+  #[test]
+  fn resolves_order_within_stages() {
+    let schedule = Scheduler::new()
+      .with(TestSystem::new("draw", Stage::Render))
+      .with(TestSystem::new("damage", Stage::Simulation).after("collision"))
+      .with(TestSystem::new("collision", Stage::Simulation).after("physics"))
+      .with(TestSystem::new("physics", Stage::Simulation).after("input"))
+      .with(TestSystem::new("expiration", Stage::Simulation).before("collision"))
+      .with(TestSystem::new("input", Stage::Input))
+      .build()
+      .unwrap();
+
+    assert_eq!(schedule.labels(Stage::Input), vec!["input"]);
+    assert_eq!(
+      schedule.labels(Stage::Simulation),
+      vec!["physics", "expiration", "collision", "damage"]
+    );
+    assert!(schedule.labels(Stage::PostSimulation).is_empty());
+    assert_eq!(schedule.labels(Stage::Render), vec!["draw"]);
+  }
+
+  #[test]
+  fn rejects_cycles() {
+    let result = Scheduler::new()
+      .with(TestSystem::new("a", Stage::Simulation).after("c"))
+      .with(TestSystem::new("b", Stage::Simulation).after("a"))
+      .with(TestSystem::new("c", Stage::Simulation).after("b"))
+      .with(TestSystem::new("d", Stage::Simulation))
+      .build();
+
+    assert_eq!(
+      result.err(),
+      Some(ScheduleError::Cycle(vec!["a", "b", "c"]))
+    );
+  }
+
+  #[test]
+  fn rejects_invalid_constraints() {
+    let unknown = Scheduler::new()
+      .with(TestSystem::new("a", Stage::Simulation).after("missing"))
+      .build();
+    let duplicate = Scheduler::new()
+      .with(TestSystem::new("a", Stage::Simulation))
+      .with(TestSystem::new("a", Stage::Render))
+      .build();
+    let conflict = Scheduler::new()
+      .with(TestSystem::new("a", Stage::Input).after("b"))
+      .with(TestSystem::new("b", Stage::Render))
+      .build();
+
+    assert_eq!(
+      unknown.err(),
+      Some(ScheduleError::UnknownLabel("a", "missing"))
+    );
+    assert_eq!(duplicate.err(), Some(ScheduleError::DuplicateLabel("a")));
+    assert_eq!(conflict.err(), Some(ScheduleError::StageConflict("a", "b")));
+  }
+
+  #[test]
+  fn batches_systems_without_conflicting_access() {
+    struct A;
+    struct B;
+    struct C;
+
+    let schedule = Scheduler::new()
+      .with(
+        TestSystem::new("writes_a", Stage::Simulation)
+          .access(SystemAccess::new().writes::<A>().reads::<B>()),
+      )
+      .with(
+        TestSystem::new("reads_c", Stage::Simulation)
+          .access(SystemAccess::new().reads::<C>().reads::<B>()),
+      )
+      .with(TestSystem::new("reads_a", Stage::Simulation).access(SystemAccess::new().reads::<A>()))
+      .with(
+        TestSystem::new("writes_c", Stage::Simulation)
+          .access(SystemAccess::new().writes::<C>())
+          .after("reads_a"),
+      )
+      .with(
+        TestSystem::new("writes_b", Stage::Simulation)
+          .access(SystemAccess::new().writes::<B>())
+          .after("writes_c"),
+      )
+      .with(TestSystem::new("exclusive", Stage::Simulation))
+      .build()
+      .unwrap();
+
+    assert_eq!(
+      schedule.batches(Stage::Simulation),
+      vec![
+        vec!["writes_a", "reads_c"],
+        vec!["reads_a"],
+        vec!["writes_c"],
+        vec!["writes_b"],
+        vec!["exclusive"],
+      ]
+    );
+  }
+
+  #[test]
+  fn headless_runs_refuse_systems_that_need_the_context() {
+    let mut schedule = Scheduler::new()
+      .with(TestSystem::new("simulate", Stage::Simulation).access(SystemAccess::new()))
+      .with(TestSystem::new("draw", Stage::Render).access(SystemAccess::new().context()))
+      .build()
+      .unwrap();
+    let mut world = World::new();
+    let mut commands = Commands::new();
+
+    assert!(schedule
+      .run_headless(Stage::Simulation, &mut world, &mut commands)
+      .is_ok());
+    assert!(schedule
+      .run_headless(Stage::Render, &mut world, &mut commands)
+      .is_err());
+  }
+
+  #[test]
+  fn access_conflicts() {
+    struct A;
+    struct B;
+
+    let reads_a = SystemAccess::new().reads::<A>();
+    let writes_a = SystemAccess::new().writes::<A>();
+    let writes_b = SystemAccess::new().writes::<B>().context();
+
+    assert!(!reads_a.conflicts_with(&reads_a));
+    assert!(reads_a.conflicts_with(&writes_a));
+    assert!(writes_a.conflicts_with(&writes_a));
+    assert!(!writes_a.conflicts_with(&writes_b));
+    assert!(SystemAccess::exclusive().conflicts_with(&SystemAccess::new()));
+  }
+}
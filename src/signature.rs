@@ -0,0 +1,96 @@
+/// Bitset over registered component ids, recording which components an
+/// entity currently has.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Signature {
+  blocks: Vec<u64>,
+}
+
+impl Signature {
+  pub fn new() -> Self {
+    Signature { blocks: vec![] }
+  }
+
+  pub fn insert(&mut self, id: usize) {
+    let (block, bit) = (id / 64, id % 64);
+
+    if block >= self.blocks.len() {
+      self.blocks.resize(block + 1, 0);
+    }
+
+    self.blocks[block] |= 1 << bit;
+  }
+
+  pub fn remove(&mut self, id: usize) {
+    let (block, bit) = (id / 64, id % 64);
+
+    if let Some(block) = self.blocks.get_mut(block) {
+      *block &= !(1 << bit);
+    }
+  }
+
+  pub fn contains(&self, id: usize) -> bool {
+    let (block, bit) = (id / 64, id % 64);
+
+    self
+      .blocks
+      .get(block)
+      .is_some_and(|block| block & (1 << bit) != 0)
+  }
+
+  pub fn contains_all(&self, other: &Signature) -> bool {
+    other.blocks.iter().enumerate().all(|(i, required)| {
+      let block = self.blocks.get(i).copied().unwrap_or(0);
+      block & required == *required
+    })
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+    self.blocks.iter().enumerate().flat_map(|(i, block)| {
+      (0..64)
+        .filter(move |bit| block & (1 << bit) != 0)
+        .map(move |bit| i * 64 + bit)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn can_insert_and_remove_ids() {
+    let mut signature = Signature::new();
+
+    signature.insert(3);
+    signature.insert(70);
+
+    assert!(signature.contains(3));
+    assert!(signature.contains(70));
+    assert!(!signature.contains(4));
+
+    signature.remove(3);
+
+    assert!(!signature.contains(3));
+    assert_eq!(signature.iter().collect::<Vec<usize>>(), vec![70]);
+  }
+
+  #[test]
+  fn contains_all_of_a_subset() {
+    let mut signature = Signature::new();
+    signature.insert(1);
+    signature.insert(2);
+    signature.insert(65);
+
+    let mut subset = Signature::new();
+    subset.insert(1);
+    subset.insert(65);
+
+    let mut other = Signature::new();
+    other.insert(1);
+    other.insert(130);
+
+    assert!(signature.contains_all(&subset));
+    assert!(!signature.contains_all(&other));
+    assert!(signature.contains_all(&Signature::new()));
+  }
+}
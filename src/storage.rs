@@ -0,0 +1,384 @@
+use crate::components::{Component, StorageKind};
+use crate::entity::EntityId;
+use std::any::Any;
+use std::slice;
+
+pub type EntityIter<'a> = slice::Iter<'a, EntityId>;
+
+/// Type-erased view of a `ComponentStorage` so `World` can hold one per
+/// registered component type.
+pub trait Storage: Any + Send + Sync {
+  /// The stable name of the stored component type.
+  fn name(&self) -> &'static str;
+  fn get_dyn(&self, entity: &EntityId) -> Option<&dyn Component>;
+  fn get_dyn_mut(&mut self, entity: &EntityId, tick: u32) -> Option<&mut dyn Component>;
+  fn remove(&mut self, entity: &EntityId);
+  fn contains(&self, entity: &EntityId) -> bool;
+  fn entities(&self) -> &[EntityId];
+  fn len(&self) -> usize;
+  fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// World change ticks at which a component was inserted and last mutably
+/// borrowed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ComponentTicks {
+  pub added: u32,
+  pub changed: u32,
+}
+
+impl ComponentTicks {
+  pub fn new(tick: u32) -> Self {
+    ComponentTicks {
+      added: tick,
+      changed: tick,
+    }
+  }
+
+  #[allow(dead_code, reason = "only read by the `Added` filter")]
+  pub fn is_added(&self, last_run: u32) -> bool {
+    self.added > last_run
+  }
+
+  #[allow(dead_code, reason = "only read by the `Changed` filter")]
+  pub fn is_changed(&self, last_run: u32) -> bool {
+    self.changed > last_run
+  }
+}
+
+/// Sparse set of components keyed by entity index. Components are packed
+/// contiguously in `components`, with `entities` holding the owner of each
+/// slot and `sparse` mapping an entity index to its slot.
+pub struct ComponentStorage<T: Component> {
+  sparse: Vec<Option<usize>>,
+  entities: Vec<EntityId>,
+  components: Vec<T>,
+  ticks: Vec<ComponentTicks>,
+  kind: StorageKind,
+}
+
+impl<T: Component> ComponentStorage<T> {
+  pub fn new() -> Self {
+    ComponentStorage::with_kind(StorageKind::SparseSet)
+  }
+
+  pub fn with_kind(kind: StorageKind) -> Self {
+    ComponentStorage {
+      sparse: vec![],
+      entities: vec![],
+      components: vec![],
+      ticks: vec![],
+      kind,
+    }
+  }
+
+  pub fn get(&self, entity: &EntityId) -> Option<&T> {
+    self.slot(entity).map(|slot| &self.components[slot])
+  }
+
+  /// Mutably borrows the component, marking it changed at `tick`.
+  pub fn get_mut(&mut self, entity: &EntityId, tick: u32) -> Option<&mut T> {
+    let slot = self.slot(entity)?;
+    self.ticks[slot].changed = tick;
+
+    Some(&mut self.components[slot])
+  }
+
+  #[cfg(test)]
+  pub fn ticks(&self, entity: &EntityId) -> Option<&ComponentTicks> {
+    self.slot(entity).map(|slot| &self.ticks[slot])
+  }
+
+  pub fn insert(&mut self, entity: &EntityId, component: T, tick: u32) {
+    let index = entity.index() as usize;
+
+    if index >= self.sparse.len() {
+      self.sparse.resize(index + 1, None);
+    }
+
+    match self.sparse[index] {
+      Some(slot) if self.entities[slot] == *entity => {
+        self.components[slot] = component;
+        self.ticks[slot].changed = tick;
+      }
+      Some(slot) => {
+        // a stale generation in this slot is simply overwritten
+        self.entities[slot] = *entity;
+        self.components[slot] = component;
+        self.ticks[slot] = ComponentTicks::new(tick);
+      }
+      None => {
+        self.sparse[index] = Some(self.components.len());
+        self.entities.push(*entity);
+        self.components.push(component);
+        self.ticks.push(ComponentTicks::new(tick));
+      }
+    }
+  }
+
+  pub fn take(&mut self, entity: &EntityId) -> Option<T> {
+    let slot = self.slot(entity)?;
+    self.sparse[entity.index() as usize] = None;
+
+    if self.kind == StorageKind::Ordered {
+      self.entities.remove(slot);
+      self.ticks.remove(slot);
+      for (moved, owner) in self.entities[slot..].iter().enumerate() {
+        self.sparse[owner.index() as usize] = Some(slot + moved);
+      }
+
+      return Some(self.components.remove(slot));
+    }
+
+    let last = *self.entities.last()?;
+    if last != *entity {
+      self.sparse[last.index() as usize] = Some(slot);
+    }
+    self.entities.swap_remove(slot);
+    self.ticks.swap_remove(slot);
+
+    Some(self.components.swap_remove(slot))
+  }
+
+  pub fn components(&self) -> &[T] {
+    &self.components
+  }
+
+  pub fn entity_iter(&self) -> EntityIter<'_> {
+    self.entities.iter()
+  }
+
+  /// Splits the storage into its lookup tables and raw pointers to the packed
+  /// components and their ticks, so a query can hand out disjoint `&mut T`s.
+  pub(crate) fn split_mut(
+    &mut self,
+  ) -> (&[Option<usize>], &[EntityId], *mut T, *mut ComponentTicks) {
+    (
+      &self.sparse,
+      &self.entities,
+      self.components.as_mut_ptr(),
+      self.ticks.as_mut_ptr(),
+    )
+  }
+
+  /// The lookup tables and a raw pointer to the ticks, so a filter can check
+  /// presence and ticks without borrowing the packed arrays while a query
+  /// hands out `&mut T`s from `split_mut`.
+  pub(crate) fn split(&self) -> (&[Option<usize>], &[EntityId], *const ComponentTicks) {
+    (&self.sparse, &self.entities, self.ticks.as_ptr())
+  }
+
+  fn slot(&self, entity: &EntityId) -> Option<usize> {
+    slot_of(&self.sparse, &self.entities, entity)
+  }
+}
+
+impl<T: Component> Default for ComponentStorage<T> {
+  fn default() -> Self {
+    ComponentStorage::new()
+  }
+}
+
+impl<T: Component> Storage for ComponentStorage<T> {
+  fn name(&self) -> &'static str {
+    T::name()
+  }
+
+  fn get_dyn(&self, entity: &EntityId) -> Option<&dyn Component> {
+    self.get(entity).map(|c| c as &dyn Component)
+  }
+
+  fn get_dyn_mut(&mut self, entity: &EntityId, tick: u32) -> Option<&mut dyn Component> {
+    self.get_mut(entity, tick).map(|c| c as &mut dyn Component)
+  }
+
+  fn remove(&mut self, entity: &EntityId) {
+    self.take(entity);
+  }
+
+  fn contains(&self, entity: &EntityId) -> bool {
+    self.slot(entity).is_some()
+  }
+
+  fn entities(&self) -> &[EntityId] {
+    &self.entities
+  }
+
+  fn len(&self) -> usize {
+    self.components.len()
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// Looks up the slot of `entity` in the lookup tables returned by
+/// `ComponentStorage::split_mut`.
+pub(crate) fn slot_of(
+  sparse: &[Option<usize>],
+  entities: &[EntityId],
+  entity: &EntityId,
+) -> Option<usize> {
+  let slot = (*sparse.get(entity.index() as usize)?)?;
+
+  if entities[slot] == *entity {
+    Some(slot)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::allocator::EntityAllocator;
+  use crate::components::Transform;
+  use ggez::nalgebra::Point2;
+
+  #[test]
+  fn can_add_and_remove_components() {
+    let transform1 = Transform::new(0., 0.);
+    let transform2 = Transform::new(1., 1.);
+    let mut allocator = EntityAllocator::new();
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+
+    let mut storage = ComponentStorage::new();
+
+    storage.insert(&entity1, transform1, 0);
+    storage.insert(&entity2, transform2, 0);
+
+    let transform1 = storage.get(&entity1);
+    assert!(transform1.is_some());
+    assert_eq!(transform1.unwrap().position, Point2::new(0.0f32, 0.0f32));
+
+    storage.remove(&entity1);
+    let transform1 = storage.get(&entity1);
+    assert!(transform1.is_none());
+
+    let transform2 = storage.get(&entity2);
+    assert!(transform2.is_some());
+    assert_eq!(transform2.unwrap().position, Point2::new(1.0f32, 1.0f32));
+  }
+
+  #[test]
+  fn can_reference_entity_ids() {
+    let transform1 = Transform::new(0., 0.);
+    let transform2 = Transform::new(1., 1.);
+    let mut allocator = EntityAllocator::new();
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+
+    let mut storage = ComponentStorage::new();
+
+    storage.insert(&entity1, transform1, 0);
+    storage.insert(&entity2, transform2, 0);
+
+    assert_eq!(storage.entities(), &[entity1, entity2]);
+  }
+
+  #[test]
+  fn can_reference_components() {
+    let transform1 = Transform::new(0., 0.);
+    let transform2 = Transform::new(1., 1.);
+    let mut allocator = EntityAllocator::new();
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+    let expected_point1 = transform1.position;
+    let expected_point2 = transform2.position;
+
+    let mut storage = ComponentStorage::new();
+
+    storage.insert(&entity1, transform1, 0);
+    storage.insert(&entity2, transform2, 0);
+
+    let transforms = storage.components();
+
+    assert_eq!(transforms.len(), 2);
+    assert_eq!(transforms[0].position, expected_point1);
+    assert_eq!(transforms[1].position, expected_point2);
+  }
+
+  #[test]
+  fn keeps_components_packed_after_removal() {
+    let mut allocator = EntityAllocator::new();
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+    let entity3 = allocator.allocate();
+
+    let mut storage = ComponentStorage::new();
+
+    storage.insert(&entity1, Transform::new(1., 1.), 0);
+    storage.insert(&entity2, Transform::new(2., 2.), 0);
+    storage.insert(&entity3, Transform::new(3., 3.), 0);
+    storage.remove(&entity1);
+
+    assert_eq!(storage.len(), 2);
+    assert_eq!(storage.entities(), &[entity3, entity2]);
+    assert_eq!(storage.get(&entity3).unwrap().position, Point2::new(3., 3.));
+    assert_eq!(storage.get(&entity2).unwrap().position, Point2::new(2., 2.));
+  }
+
+  #[test]
+  fn ordered_storage_keeps_insertion_order_after_removal() {
+    let mut allocator = EntityAllocator::new();
+    let entity1 = allocator.allocate();
+    let entity2 = allocator.allocate();
+    let entity3 = allocator.allocate();
+
+    let mut storage = ComponentStorage::with_kind(StorageKind::Ordered);
+
+    storage.insert(&entity1, Transform::new(1., 1.), 0);
+    storage.insert(&entity2, Transform::new(2., 2.), 0);
+    storage.insert(&entity3, Transform::new(3., 3.), 0);
+    storage.remove(&entity1);
+
+    assert_eq!(storage.entities(), &[entity2, entity3]);
+    assert_eq!(storage.get(&entity2).unwrap().position, Point2::new(2., 2.));
+    assert_eq!(storage.get(&entity3).unwrap().position, Point2::new(3., 3.));
+  }
+
+  #[test]
+  fn ignores_stale_entities() {
+    let mut allocator = EntityAllocator::new();
+    let entity1 = allocator.allocate();
+    allocator.free(&entity1);
+    let entity2 = allocator.allocate();
+
+    let mut storage = ComponentStorage::new();
+
+    storage.insert(&entity1, Transform::new(1., 1.), 0);
+    storage.insert(&entity2, Transform::new(2., 2.), 0);
+
+    assert_eq!(storage.len(), 1);
+    assert!(storage.get(&entity1).is_none());
+    assert!(!storage.contains(&entity1));
+    assert_eq!(storage.get(&entity2).unwrap().position, Point2::new(2., 2.));
+  }
+
+  #[test]
+  fn tracks_when_components_are_added_and_changed() {
+    let mut allocator = EntityAllocator::new();
+    let entity = allocator.allocate();
+
+    let mut storage = ComponentStorage::new();
+
+    storage.insert(&entity, Transform::new(1., 1.), 1);
+    assert_eq!(storage.ticks(&entity), Some(&ComponentTicks::new(1)));
+
+    storage.get_mut(&entity, 2);
+    storage.insert(&entity, Transform::new(2., 2.), 3);
+
+    let ticks = storage.ticks(&entity).unwrap();
+    assert!(ticks.is_added(0));
+    assert!(!ticks.is_added(1));
+    assert!(ticks.is_changed(2));
+    assert_eq!(ticks.changed, 3);
+  }
+}
@@ -1,3 +1,5 @@
+use crate::commands::Commands;
+use crate::scheduler::{Stage, SystemAccess};
 use crate::world::World;
 use ggez::Context;
 use ggez::GameResult;
@@ -10,6 +12,7 @@ mod physics;
 mod player_input;
 mod shape_manager;
 mod ship_manager;
+mod transform;
 
 pub use collision::CollisionSystem;
 pub use damage::DamageSystem;
@@ -19,7 +22,57 @@ pub use physics::PhysicsSystem;
 pub use player_input::PlayerInputSystem;
 pub use shape_manager::ShapeManager;
 pub use ship_manager::ShipManager;
+pub use transform::TransformSystem;
 
-pub trait System {
-  fn update(world: &mut World, context: &mut Context) -> GameResult;
+pub mod labels {
+  pub const PLAYER_INPUT: &str = "player_input";
+  pub const PHYSICS: &str = "physics";
+  pub const TRANSFORM: &str = "transform";
+  pub const EXPIRATION: &str = "expiration";
+  pub const COLLISION: &str = "collision";
+  pub const DAMAGE: &str = "damage";
+  pub const SHIP_MANAGER: &str = "ship_manager";
+  pub const SHAPE_MANAGER: &str = "shape_manager";
+  pub const DRAW: &str = "draw";
+}
+
+pub trait System: Send {
+  fn label(&self) -> &'static str;
+
+  fn stage(&self) -> Stage {
+    Stage::Simulation
+  }
+
+  /// Labels of systems this one must run before.
+  fn before(&self) -> Vec<&'static str> {
+    vec![]
+  }
+
+  /// Labels of systems this one must run after.
+  fn after(&self) -> Vec<&'static str> {
+    vec![]
+  }
+
+  /// Types this system reads and writes. Systems that don't declare their
+  /// access never run alongside others.
+  fn access(&self) -> SystemAccess {
+    SystemAccess::exclusive()
+  }
+
+  /// Runs the system on the main thread. Systems whose access includes
+  /// `SystemAccess::context` override this, everything else implements `run`.
+  fn update(
+    &mut self,
+    world: &mut World,
+    commands: &mut Commands,
+    _context: &mut Context,
+  ) -> GameResult {
+    self.run(world, commands)
+  }
+
+  /// Runs the system without the ggez context, which lets the scheduler move
+  /// it to a worker thread.
+  fn run(&mut self, _world: &mut World, _commands: &mut Commands) -> GameResult {
+    Ok(())
+  }
 }
@@ -1,46 +1,54 @@
+use super::labels;
 use super::System;
-use crate::components::Collision;
+use crate::commands::Commands;
 use crate::components::Damage;
-use crate::components::Damaged;
 use crate::components::Vulnerable;
 use crate::entity::EntityId;
+use crate::events::{CollisionEvent, DamagedEvent, ReaderId};
+use crate::scheduler::SystemAccess;
 use crate::world::World;
-use ggez::Context;
 use ggez::GameResult;
 
 pub struct DamageSystem;
 
 impl System for DamageSystem {
-  fn update(world: &mut World, _context: &mut Context) -> GameResult {
-    world.remove_all(world.entities::<Damaged>());
+  fn label(&self) -> &'static str {
+    labels::DAMAGE
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![labels::COLLISION]
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .reads::<CollisionEvent>()
+      .reads::<Damage>()
+      .reads::<Vulnerable>()
+      .writes::<DamagedEvent>()
+  }
 
-    let mut damaged: Vec<Damaged> = vec![];
-    let collisions: Vec<(EntityId, EntityId)> = world
-      .components::<Collision>()
-      .into_iter()
-      .map(|c| (c.entity1, c.entity2))
-      .collect();
+  fn run(&mut self, world: &mut World, commands: &mut Commands) -> GameResult {
+    for collision in world.read::<CollisionEvent>(ReaderId::of::<Self>()) {
+      let (e1, e2) = (collision.entity1, collision.entity2);
+      if !world.is_alive(&e1) || !world.is_alive(&e2) {
+        continue;
+      }
 
-    for (e1, e2) in collisions {
       if is_damaged_by(world, &e1, &e2) {
-        damaged.push(Damaged::new(e1));
+        commands.send(DamagedEvent::new(e1));
       }
 
       if is_damaged_by(world, &e2, &e1) {
-        damaged.push(Damaged::new(e2));
+        commands.send(DamagedEvent::new(e2));
       }
     }
 
-    for d in damaged {
-      let e = world.create_entity();
-      world.add(&e, d);
-    }
-
     Ok(())
   }
 }
 
-fn is_damaged_by(world: &mut World, entity1: &EntityId, entity2: &EntityId) -> bool {
+fn is_damaged_by(world: &World, entity1: &EntityId, entity2: &EntityId) -> bool {
   let damage = world.get::<Damage>(entity2);
   let vulnerability = world.get::<Vulnerable>(entity1);
 
@@ -1,42 +1,97 @@
+use crate::commands::Commands;
 use crate::components::Drawable;
-use crate::components::Transform;
-use crate::entity::EntityId;
+use crate::components::GlobalTransform;
+use crate::mesh::MeshCache;
+use crate::resources::{ArenaSize, FixedTime};
 use crate::world::World;
-use ggez::graphics;
+use ggez::graphics::{self, Mesh};
+use ggez::nalgebra::Point2;
 use ggez::Context;
 use ggez::GameResult;
-use std::any::TypeId;
 
+use super::labels;
 use super::System;
+use crate::scheduler::{Stage, SystemAccess};
 
-pub struct DrawSystem;
+pub struct DrawSystem {
+  meshes: MeshCache,
+}
+
+impl DrawSystem {
+  pub fn new() -> Self {
+    DrawSystem {
+      meshes: MeshCache::new(),
+    }
+  }
+}
 
 impl System for DrawSystem {
-  fn update(world: &mut World, context: &mut Context) -> GameResult {
-    let drawables = world.entities_with(vec![TypeId::of::<Drawable>(), TypeId::of::<Transform>()]);
+  fn label(&self) -> &'static str {
+    labels::DRAW
+  }
 
-    for drawable in drawables {
-      draw(world, &drawable, context)?
+  fn stage(&self) -> Stage {
+    Stage::Render
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .context()
+      .reads::<Drawable>()
+      .reads::<GlobalTransform>()
+      .reads::<FixedTime>()
+      .reads::<ArenaSize>()
+  }
+
+  fn update(
+    &mut self,
+    world: &mut World,
+    _commands: &mut Commands,
+    context: &mut Context,
+  ) -> GameResult {
+    let alpha = world.resource::<FixedTime>()?.alpha();
+    let arena = *world.resource::<ArenaSize>()?;
+
+    for (_, (drawable, transform)) in world.query::<(&Drawable, &GlobalTransform)>() {
+      let mesh = self.meshes.get(context, drawable)?;
+      draw(
+        mesh,
+        drawable,
+        interpolate(transform, alpha, &arena),
+        context,
+      )?
     }
 
     Ok(())
   }
 }
 
-fn draw(world: &World, entity: &EntityId, context: &mut Context) -> GameResult {
-  let drawable = world.get::<Drawable>(entity);
-  let transform = world.get::<Transform>(entity);
-
-  if let (Some(drawable), Some(transform)) = (drawable, transform) {
-    graphics::draw(
-      context,
-      &drawable.mesh,
-      graphics::DrawParam::default()
-        .dest(transform.position.clone())
-        .rotation(transform.rotation.to_radians())
-        .offset(drawable.offset),
-    )?;
+/// Where to draw `transform` between steps. Entities that wrapped around the
+/// arena last step are drawn where they are rather than swept across it.
+fn interpolate(transform: &GlobalTransform, alpha: f32, arena: &ArenaSize) -> (Point2<f32>, f32) {
+  match transform.previous {
+    Some((previous, _))
+      if (transform.position.x - previous.x).abs() > arena.width / 2.
+        || (transform.position.y - previous.y).abs() > arena.height / 2. =>
+    {
+      (transform.position, transform.rotation)
+    }
+    _ => transform.interpolate(alpha),
   }
+}
 
-  Ok(())
+fn draw(
+  mesh: &Mesh,
+  drawable: &Drawable,
+  (position, rotation): (Point2<f32>, f32),
+  context: &mut Context,
+) -> GameResult {
+  graphics::draw(
+    context,
+    mesh,
+    graphics::DrawParam::default()
+      .dest(position)
+      .rotation(rotation.to_radians())
+      .offset(drawable.offset),
+  )
 }
@@ -1,28 +1,71 @@
+use super::labels;
 use super::System;
+use crate::commands::Commands;
 use crate::components::Expirable;
-use crate::entity::EntityId;
+use crate::resources::GameClock;
+use crate::scheduler::SystemAccess;
 use crate::world::World;
-use ggez::Context;
 use ggez::GameResult;
 
 pub struct ExpirationSystem;
 
 impl System for ExpirationSystem {
-  fn update(world: &mut World, _context: &mut Context) -> GameResult {
-    let expired: Vec<EntityId> = world
-      .entities::<Expirable>()
-      .into_iter()
-      .filter(|e| !should_keep(world, &e))
-      .collect();
+  fn label(&self) -> &'static str {
+    labels::EXPIRATION
+  }
+
+  fn before(&self) -> Vec<&'static str> {
+    vec![labels::COLLISION]
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .reads::<Expirable>()
+      .reads::<GameClock>()
+  }
+
+  fn run(&mut self, world: &mut World, commands: &mut Commands) -> GameResult {
+    let clock = *world.resource::<GameClock>()?;
 
-    world.remove_all(expired);
+    for (entity, expirable) in world.query::<&Expirable>() {
+      if expirable.is_expired(&clock) {
+        commands.despawn(entity);
+      }
+    }
 
     Ok(())
   }
 }
 
-fn should_keep(world: &World, entity: &EntityId) -> bool {
-  world
-    .get::<Expirable>(entity)
-    .map_or(true, |e| !e.is_expired())
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn despawns_entities_once_their_lifetime_has_passed() {
+    let mut world = World::new();
+    let mut clock = GameClock::new();
+    clock.advance(Duration::from_secs(1));
+    world.insert_resource(clock);
+
+    let entity = world.create_entity();
+    world.add(
+      &entity,
+      Expirable::new(clock.elapsed(), Duration::from_secs(2)),
+    );
+
+    let expire = |world: &mut World, by: Duration| {
+      world.resource_mut::<GameClock>().unwrap().advance(by);
+      let mut commands = Commands::new();
+      ExpirationSystem.run(world, &mut commands).unwrap();
+      world.apply(&mut commands);
+    };
+
+    expire(&mut world, Duration::from_secs(2));
+    assert!(world.is_alive(&entity));
+
+    expire(&mut world, Duration::from_millis(1));
+    assert!(!world.is_alive(&entity));
+  }
 }
@@ -1,67 +1,73 @@
+use crate::commands::Commands;
 use crate::components::Physicsable;
 use crate::components::Transform;
-use crate::entity::EntityId;
+use crate::resources::{ArenaSize, FixedTime};
+use crate::scheduler::SystemAccess;
 use crate::world::World;
-use ggez::graphics;
-use ggez::Context;
 use ggez::GameResult;
 
+use super::labels;
 use super::System;
 
-const MAX_VELOCITY: f32 = 5.0;
+/// Pixels per second.
+const MAX_VELOCITY: f32 = 300.0;
 
 pub struct PhysicsSystem;
 
 impl System for PhysicsSystem {
-  fn update(world: &mut World, context: &mut Context) -> GameResult {
-    let entities: Vec<EntityId> = world.entities::<Physicsable>();
+  fn label(&self) -> &'static str {
+    labels::PHYSICS
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .writes::<Transform>()
+      .writes::<Physicsable>()
+      .reads::<ArenaSize>()
+      .reads::<FixedTime>()
+  }
+
+  fn run(&mut self, world: &mut World, _commands: &mut Commands) -> GameResult {
+    let arena = *world.resource::<ArenaSize>()?;
+    let delta = world.resource::<FixedTime>()?.delta();
 
-    for entity in entities {
-      handle_acceleration(world, &entity);
-      handle_velocity(world, &entity);
-      wrap_position(world, context, &entity);
+    for (_, (transform, physics)) in world.query::<(&mut Transform, &mut Physicsable)>() {
+      handle_acceleration(physics, delta);
+      handle_velocity(transform, physics, delta);
+      wrap_position(transform, &arena);
     }
 
     Ok(())
   }
 }
 
-fn handle_acceleration(world: &mut World, entity: &EntityId) {
-  if let Some(physics) = world.get_mut::<Physicsable>(entity) {
-    physics.velocity += physics.acceleration;
-    if physics.velocity.norm_squared() > MAX_VELOCITY.powi(2) {
-      physics.velocity = physics.velocity / physics.velocity.norm_squared().sqrt() * MAX_VELOCITY;
-    }
+fn handle_acceleration(physics: &mut Physicsable, delta: f32) {
+  physics.velocity += physics.acceleration * delta;
+  if physics.velocity.norm_squared() > MAX_VELOCITY.powi(2) {
+    physics.velocity = physics.velocity / physics.velocity.norm_squared().sqrt() * MAX_VELOCITY;
   }
 }
 
-fn handle_velocity(world: &mut World, entity: &EntityId) {
-  if let Some(physics) = world.get::<Physicsable>(entity) {
-    let velocity = physics.velocity.clone();
-    if let Some(position) = world.get_mut::<Transform>(entity) {
-      position.position += velocity;
-    }
-  }
+fn handle_velocity(transform: &mut Transform, physics: &Physicsable, delta: f32) {
+  transform.position += physics.velocity * delta;
 }
 
-fn wrap_position(world: &mut World, context: &mut Context, entity: &EntityId) {
-  if let Some(position) = world.get_mut::<Transform>(entity) {
-    let (screen_width, screen_height) = graphics::drawable_size(context);
+fn wrap_position(position: &mut Transform, arena: &ArenaSize) {
+  let (screen_width, screen_height) = (arena.width, arena.height);
 
-    if position.position.x < 0.0 {
-      position.position.x += screen_width;
-    }
+  if position.position.x < 0.0 {
+    position.position.x += screen_width;
+  }
 
-    if position.position.x > screen_width {
-      position.position.x -= screen_width;
-    }
+  if position.position.x > screen_width {
+    position.position.x -= screen_width;
+  }
 
-    if position.position.y < 0.0 {
-      position.position.y += screen_height;
-    }
+  if position.position.y < 0.0 {
+    position.position.y += screen_height;
+  }
 
-    if position.position.y > screen_height {
-      position.position.y -= screen_height;
-    }
+  if position.position.y > screen_height {
+    position.position.y -= screen_height;
   }
 }
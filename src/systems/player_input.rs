@@ -1,91 +1,117 @@
+use crate::commands::Commands;
 use crate::components::Physicsable;
 use crate::components::PlayerControllable;
 use crate::components::Transform;
-use crate::entity::{Bullet, EntityId};
 use crate::geometry;
+use crate::resources::{FireCooldown, FrameTime, GameClock, InputState};
 use crate::world::World;
 use ggez::event::KeyCode;
-use ggez::input::keyboard;
-use ggez::nalgebra::Point2;
-use ggez::Context;
 use ggez::GameResult;
-use std::time::Instant;
 
+use super::labels;
 use super::System;
+use crate::scheduler::{Stage, SystemAccess};
 
 pub struct PlayerInputSystem;
 
 impl System for PlayerInputSystem {
-  fn update(world: &mut World, context: &mut Context) -> GameResult {
-    let entities: Vec<EntityId> = world.entities::<PlayerControllable>();
+  fn label(&self) -> &'static str {
+    labels::PLAYER_INPUT
+  }
+
+  fn stage(&self) -> Stage {
+    Stage::Input
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .writes::<Transform>()
+      .writes::<Physicsable>()
+      .writes::<PlayerControllable>()
+      .reads::<FireCooldown>()
+      .reads::<FrameTime>()
+      .reads::<GameClock>()
+      .reads::<InputState>()
+  }
 
-    for entity in entities {
-      apply_inputs_to(world, context, &entity);
-      handle_fire(world, context, &entity)?;
+  fn run(&mut self, world: &mut World, commands: &mut Commands) -> GameResult {
+    let cooldown = *world.resource::<FireCooldown>()?;
+    let delta = world.resource::<FrameTime>()?.delta();
+    let clock = *world.resource::<GameClock>()?;
+    let input = world.resource::<InputState>()?.clone();
+
+    for (_, (transform, physics, controllable)) in
+      world.query::<(&mut Transform, &mut Physicsable, &mut PlayerControllable)>()
+    {
+      apply_inputs_to(&input, transform, physics, delta);
+
+      if should_fire(&input, controllable, &clock, &cooldown) {
+        commands.spawn_prefab("bullet", transform.clone());
+      }
     }
 
     Ok(())
   }
 }
 
-fn apply_inputs_to(world: &mut World, context: &mut Context, entity: &EntityId) {
-  let rotation = if let Some(transform) = world.get_mut::<Transform>(entity) {
-    if keyboard::is_key_pressed(context, KeyCode::A) {
-      turn_left(transform);
-    }
-    if keyboard::is_key_pressed(context, KeyCode::D) {
-      turn_right(transform);
-    }
+fn apply_inputs_to(
+  input: &InputState,
+  transform: &mut Transform,
+  physics: &mut Physicsable,
+  delta: f32,
+) {
+  if input.is_pressed(KeyCode::A) {
+    turn_left(transform, delta);
+  }
+  if input.is_pressed(KeyCode::D) {
+    turn_right(transform, delta);
+  }
 
-    transform.rotation.clone()
+  if input.is_pressed(KeyCode::W) {
+    accelerate(physics, transform.rotation, delta);
   } else {
-    0.
-  };
-
-  if let Some(physics) = world.get_mut::<Physicsable>(entity) {
-    if keyboard::is_key_pressed(context, KeyCode::W) {
-      accelerate(physics, rotation);
-    } else {
-      decelerate(physics);
-    }
+    decelerate(physics);
   }
 }
 
-fn handle_fire(world: &mut World, context: &mut Context, entity: &EntityId) -> GameResult {
-  let transform = world.get::<Transform>(entity);
-  let position = transform.map(|t| t.position).unwrap_or(Point2::new(0., 0.));
-  let rotation = transform.map(|t| t.rotation).unwrap_or(0.0);
-
-  if let Some(controllable) = world.get_mut::<PlayerControllable>(entity) {
-    if keyboard::is_key_pressed(context, KeyCode::Space) {
-      if controllable.last_fired.elapsed().as_secs() > 1 {
-        controllable.last_fired = Instant::now();
-        Bullet::create(world, context, position.x, position.y, rotation)?;
-      }
-    }
+fn should_fire(
+  input: &InputState,
+  controllable: &mut PlayerControllable,
+  clock: &GameClock,
+  cooldown: &FireCooldown,
+) -> bool {
+  if input.is_pressed(KeyCode::Space) && controllable.can_fire(clock, cooldown.duration) {
+    controllable.last_fired = Some(clock.elapsed());
+    return true;
   }
 
-  Ok(())
+  false
 }
 
-pub fn accelerate(physics: &mut Physicsable, rotation: f32) {
-  physics.acceleration += 0.01 * geometry::angle_to_vec(rotation);
+/// How fast acceleration builds up while thrust is held, in pixels per second
+/// cubed. It isn't the acceleration itself: each frame adds `THRUST_RAMP *
+/// delta` to it, e.g. 36 px/s² per frame at 60fps, until thrust is released.
+const THRUST_RAMP: f32 = 2160.0;
+
+pub fn accelerate(physics: &mut Physicsable, rotation: f32, delta: f32) {
+  physics.acceleration += THRUST_RAMP * delta * geometry::angle_to_vec(rotation);
 }
 
 pub fn decelerate(physics: &mut Physicsable) {
   physics.acceleration *= 0.0;
 }
 
-const ROTATION_SPEED: f32 = 3.0;
+/// Degrees per second.
+const ROTATION_SPEED: f32 = 180.0;
 
-pub fn turn_left(transform: &mut Transform) {
-  let mut rotation = transform.rotation - ROTATION_SPEED;
+pub fn turn_left(transform: &mut Transform, delta: f32) {
+  let mut rotation = transform.rotation - ROTATION_SPEED * delta;
   if rotation < 0.0 {
     rotation += 360.0;
   }
   transform.rotation = rotation
 }
 
-pub fn turn_right(transform: &mut Transform) {
-  transform.rotation = (transform.rotation + ROTATION_SPEED) % 360.0;
+pub fn turn_right(transform: &mut Transform, delta: f32) {
+  transform.rotation = (transform.rotation + ROTATION_SPEED * delta) % 360.0;
 }
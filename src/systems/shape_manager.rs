@@ -1,55 +1,76 @@
+use super::labels;
 use super::System;
-use crate::components::{Damaged, Tag, TagType, Transform};
-use crate::entity::{EntityId, Hexagon, Square};
+use crate::commands::Commands;
+use crate::components::{IsShape, PrefabName, Tag, Transform};
+use crate::entity::EntityId;
+use crate::events::{DamagedEvent, ReaderId};
+use crate::prefab::Prefabs;
+use crate::query::Tagged;
+use crate::scheduler::{Stage, SystemAccess};
 use crate::world::World;
 use ggez::nalgebra::Point2;
-use ggez::{Context, GameResult};
+use ggez::GameResult;
+use std::collections::HashSet;
 
 pub struct ShapeManager;
 
 impl System for ShapeManager {
-  fn update(world: &mut World, ctx: &mut Context) -> GameResult {
-    let is_shape = |e: &EntityId| {
-      if let Some(tag) = world.get::<Tag>(e) {
-        if let TagType::Shape(_) = tag.tag_type {
-          return true;
-        };
-      }
-      false
-    };
-
-    // TODO this either needs some sort of query or to be moved to an event system
-    let damaged_shapes: Vec<EntityId> = world
-      .components::<Damaged>()
-      .into_iter()
-      .map(|d| d.entity.clone())
-      .filter(is_shape)
+  fn label(&self) -> &'static str {
+    labels::SHAPE_MANAGER
+  }
+
+  fn stage(&self) -> Stage {
+    Stage::PostSimulation
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .reads::<DamagedEvent>()
+      .reads::<Tag>()
+      .reads::<Transform>()
+      .reads::<PrefabName>()
+      .reads::<Prefabs>()
+  }
+
+  fn run(&mut self, world: &mut World, commands: &mut Commands) -> GameResult {
+    let damaged: Vec<EntityId> = world
+      .read::<DamagedEvent>(ReaderId::of::<Self>())
+      .map(|damaged| damaged.entity)
       .collect();
 
-    for entity in damaged_shapes {
-      let tag = world.get::<Tag>(&entity).map(|t| t.tag_type.clone());
-      let transform = world.get::<Transform>(&entity).map(|t| t.position.clone());
-      if let (Some(TagType::Shape(level)), Some(position)) = (tag, transform) {
-        build_shape(world, level - 1, position, ctx)?;
+    let mut destroyed = HashSet::new();
+
+    for entity in damaged {
+      if destroyed.contains(&entity) {
+        continue;
+      }
+
+      let shape = world
+        .query_one::<(&Transform, &PrefabName, Tagged<IsShape>)>(&entity)
+        .map(|(transform, name, _)| (transform.position, name.name.clone()));
+
+      if let Some((position, name)) = shape {
+        split_shape(world, commands, &name, position)?;
+        commands.despawn(entity);
+        destroyed.insert(entity);
       }
-      world.remove(&entity);
     }
 
     Ok(())
   }
 }
 
-fn build_shape(
-  world: &mut World,
-  level: u8,
+fn split_shape(
+  world: &World,
+  commands: &mut Commands,
+  name: &str,
   position: Point2<f32>,
-  context: &mut Context,
 ) -> GameResult {
-  match level {
-    2 => Some(Hexagon::create(world, context, position.x, position.y)?),
-    1 => Some(Square::create(world, context, position.x, position.y)?),
-    _ => None,
-  };
+  if let Some(split) = &world.resource::<Prefabs>()?.get(name)?.split {
+    for _ in 0..split.count {
+      commands.spawn_prefab(&split.into, Transform::new(position.x, position.y));
+    }
+  }
 
   Ok(())
 }
@@ -1,37 +1,52 @@
+use super::labels;
 use super::System;
-use crate::components::Damaged;
-use crate::components::{Tag, TagType};
+use crate::commands::Commands;
+use crate::components::{IsShip, Tag, Transform};
 use crate::entity::EntityId;
-use crate::entity::Ship;
+use crate::events::{DamagedEvent, ReaderId};
+use crate::query::Tagged;
+use crate::scheduler::{Stage, SystemAccess};
 use crate::world::World;
-use ggez::{Context, GameResult};
+use ggez::GameResult;
 
 pub struct ShipManager;
 
 impl System for ShipManager {
-  fn update(world: &mut World, ctx: &mut Context) -> GameResult {
-    let damaged_ships: Vec<EntityId> = world
-      .components::<Damaged>()
-      .into_iter()
-      .map(|e| e.entity.clone())
-      .filter(|e| {
-        if let Some(tag) = world.get::<Tag>(e) {
-          return tag.tag_type == TagType::Ship;
-        }
-        false
-      })
+  fn label(&self) -> &'static str {
+    labels::SHIP_MANAGER
+  }
+
+  fn stage(&self) -> Stage {
+    Stage::PostSimulation
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new().reads::<DamagedEvent>().reads::<Tag>()
+  }
+
+  fn run(&mut self, world: &mut World, commands: &mut Commands) -> GameResult {
+    let damaged: Vec<EntityId> = world
+      .read::<DamagedEvent>(ReaderId::of::<Self>())
+      .map(|damaged| damaged.entity)
       .collect();
 
-    // display death animation
+    let mut ship_destroyed = false;
 
-    // delay
+    for entity in damaged {
+      if world.query_one::<Tagged<IsShip>>(&entity).is_some() {
+        // display death animation
 
-    // add new ship
-    if damaged_ships.len() > 0 {
-      Ship::create(world, ctx)?;
+        // delay
+
+        commands.despawn(entity);
+        ship_destroyed = true;
+      }
     }
 
-    world.remove_all(damaged_ships);
+    // add new ship
+    if ship_destroyed {
+      commands.spawn_prefab("ship", Transform::new(200., 200.));
+    }
 
     Ok(())
   }
@@ -0,0 +1,64 @@
+use super::labels;
+use super::System;
+use crate::commands::Commands;
+use crate::components::{Children, GlobalTransform, Parent, Transform};
+use crate::entity::EntityId;
+use crate::query::{With, Without};
+use crate::scheduler::SystemAccess;
+use crate::world::World;
+use ggez::GameResult;
+
+/// Derives every `GlobalTransform` from the local `Transform`s down each
+/// hierarchy, so attachments follow their parent.
+pub struct TransformSystem;
+
+impl System for TransformSystem {
+  fn label(&self) -> &'static str {
+    labels::TRANSFORM
+  }
+
+  fn after(&self) -> Vec<&'static str> {
+    vec![labels::PHYSICS]
+  }
+
+  fn before(&self) -> Vec<&'static str> {
+    vec![labels::COLLISION]
+  }
+
+  fn access(&self) -> SystemAccess {
+    SystemAccess::new()
+      .reads::<Transform>()
+      .reads::<Parent>()
+      .reads::<Children>()
+      .writes::<GlobalTransform>()
+  }
+
+  fn run(&mut self, world: &mut World, _commands: &mut Commands) -> GameResult {
+    let roots: Vec<EntityId> = world
+      .query::<(With<Transform>, Without<Parent>)>()
+      .map(|(entity, _)| entity)
+      .collect();
+
+    for root in roots {
+      propagate(world, &root, None);
+    }
+
+    Ok(())
+  }
+}
+
+fn propagate(world: &mut World, entity: &EntityId, parent: Option<&GlobalTransform>) {
+  let global = match (world.get::<Transform>(entity), parent) {
+    (Some(local), Some(parent)) => parent.mul_transform(local),
+    (Some(local), None) => GlobalTransform::from(local),
+    _ => return,
+  };
+
+  for child in world.children(entity) {
+    propagate(world, &child, Some(&global));
+  }
+
+  if let Some(current) = world.get_mut::<GlobalTransform>(entity) {
+    current.advance(global);
+  }
+}
@@ -0,0 +1,59 @@
+use crate::components::TagType;
+use crate::entity::EntityId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Bound, RangeBounds};
+
+/// Entities by tag, kept up to date by `World` as `Tag`s are added, changed
+/// and removed.
+#[derive(Default)]
+pub struct TagIndex {
+  entities: BTreeMap<TagType, BTreeSet<EntityId>>,
+}
+
+impl TagIndex {
+  pub fn new() -> Self {
+    TagIndex::default()
+  }
+
+  pub fn insert(&mut self, entity: EntityId, tag_type: TagType) {
+    self.entities.entry(tag_type).or_default().insert(entity);
+  }
+
+  pub fn remove(&mut self, entity: &EntityId, tag_type: &TagType) {
+    if let Some(entities) = self.entities.get_mut(tag_type) {
+      entities.remove(entity);
+      if entities.is_empty() {
+        self.entities.remove(tag_type);
+      }
+    }
+  }
+
+  pub fn get(&self, tag_type: &TagType) -> Vec<EntityId> {
+    self
+      .entities
+      .get(tag_type)
+      .map_or(vec![], |entities| entities.iter().copied().collect())
+  }
+
+  /// Shapes whose level is in `levels`.
+  pub fn shapes<R: RangeBounds<u8>>(&self, levels: R) -> Vec<EntityId> {
+    let start = match levels.start_bound() {
+      Bound::Included(level) => Bound::Included(TagType::Shape(*level)),
+      Bound::Excluded(level) => Bound::Excluded(TagType::Shape(*level)),
+      Bound::Unbounded => Bound::Included(TagType::Shape(u8::MIN)),
+    };
+    let end = match levels.end_bound() {
+      Bound::Included(level) => Bound::Included(TagType::Shape(*level)),
+      Bound::Excluded(level) => Bound::Excluded(TagType::Shape(*level)),
+      Bound::Unbounded => Bound::Included(TagType::Shape(u8::MAX)),
+    };
+
+    let shapes: BTreeSet<EntityId> = self
+      .entities
+      .range((start, end))
+      .flat_map(|(_, entities)| entities.iter().copied())
+      .collect();
+
+    shapes.into_iter().collect()
+  }
+}
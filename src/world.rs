@@ -1,73 +1,444 @@
-use crate::components::Component;
-use crate::components::{
-  Collidable, Collision, ComponentManager, Damage, Damaged, Drawable, Expirable, Physicsable,
-  PlayerControllable, Tag, Transform, Vulnerable,
-};
+use crate::allocator::EntityAllocator;
+use crate::bundle::{Bundle, EntityBuilder};
+use crate::commands::Commands;
+use crate::components::{Children, Component, Mutable, OnDespawn, Parent, Tag, TagType, Transform};
 use crate::entity::EntityId;
-use crate::vec::intersections;
-use std::any::TypeId;
+use crate::events::{Event, EventStorage, Events, ReaderId};
+use crate::hooks::{ComponentHooks, Lifecycle};
+use crate::prefab::{PrefabError, Prefabs};
+use crate::query::{self, Query, QueryIter, Ticks};
+use crate::reference_index::ReferenceIndex;
+use crate::reflect::Reflect;
+use crate::resources::{GameClock, Resource, ResourceError};
+use crate::signature::Signature;
+use crate::storage::{ComponentStorage, Storage};
+use crate::tag_index::TagIndex;
+use ggez::nalgebra::Point2;
+use std::any::{Any, TypeId};
 use std::collections::HashMap;
-use uuid::Uuid;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::RangeBounds;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+/// Cursor key for `World::removed`, kept apart from the query cursors.
+struct Removed<T>(PhantomData<T>);
+
+/// A component whose `#[entity]` field points at a despawned entity. Sent as
+/// an event when a component that reports dangling references loses its
+/// target.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DanglingRef {
+  pub component: &'static str,
+  pub entity: EntityId,
+  pub target: EntityId,
+}
+
+impl fmt::Display for DanglingRef {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} on {} references despawned entity {}",
+      self.component, self.entity, self.target
+    )
+  }
+}
+
+impl Event for DanglingRef {}
 
 pub struct World {
-  component_managers: HashMap<TypeId, ComponentManager>,
+  entities: EntityAllocator,
+  signatures: Vec<Signature>,
+  component_ids: HashMap<TypeId, usize>,
+  component_names: HashMap<&'static str, usize>,
+  storages: Vec<Box<dyn Storage>>,
+  events: HashMap<TypeId, Box<dyn EventStorage>>,
+  resources: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+  change_tick: AtomicU32,
+  change_cursors: Mutex<HashMap<(ReaderId, TypeId), u32>>,
+  removed: Vec<Vec<(EntityId, u32)>>,
+  frame_tick: u32,
+  /// Components with `#[entity]` fields and what to do when their targets
+  /// are despawned.
+  references: Vec<(usize, OnDespawn)>,
+  reference_index: ReferenceIndex,
+  strict_references: bool,
+  hooks: Vec<ComponentHooks>,
+  hook_commands: Commands,
+  tag_index: TagIndex,
 }
 
 impl World {
   pub fn new() -> Self {
-    let mut world = World {
-      component_managers: HashMap::new(),
-    };
-    world.register::<Collidable>();
-    world.register::<Collision>();
-    world.register::<Damage>();
-    world.register::<Damaged>();
-    world.register::<Drawable>();
-    world.register::<Expirable>();
-    world.register::<Physicsable>();
-    world.register::<PlayerControllable>();
-    world.register::<Tag>();
-    world.register::<Transform>();
-    world.register::<Vulnerable>();
+    World {
+      entities: EntityAllocator::new(),
+      signatures: vec![],
+      component_ids: HashMap::new(),
+      component_names: HashMap::new(),
+      storages: vec![],
+      events: HashMap::new(),
+      resources: HashMap::new(),
+      change_tick: AtomicU32::new(1),
+      change_cursors: Mutex::new(HashMap::new()),
+      removed: vec![],
+      frame_tick: 0,
+      references: vec![],
+      reference_index: ReferenceIndex::new(),
+      strict_references: cfg!(test),
+      hooks: vec![],
+      hook_commands: Commands::new(),
+      tag_index: TagIndex::new(),
+    }
+  }
+
+  pub fn create_entity(&mut self) -> EntityId {
+    let entity = self.entities.allocate();
+    let index = entity.index() as usize;
 
-    world
+    if index >= self.signatures.len() {
+      self.signatures.resize_with(index + 1, Signature::new);
+    }
+
+    entity
   }
 
-  pub fn create_entity(&self) -> EntityId {
-    Uuid::new_v4()
+  pub fn spawn(&mut self) -> EntityBuilder<'_> {
+    EntityBuilder::new(self)
   }
 
-  pub fn register<T: Component>(&mut self) {
-    self
-      .component_managers
-      .insert(TypeId::of::<T>(), ComponentManager::new());
+  pub fn spawn_bundle<B: Bundle>(&mut self, bundle: B) -> EntityId {
+    self.spawn().with(bundle).id()
+  }
+
+  pub fn spawn_prefab(
+    &mut self,
+    name: &str,
+    position: Point2<f32>,
+  ) -> Result<EntityId, PrefabError> {
+    self.spawn_prefab_at(name, Transform::new(position.x, position.y))
+  }
+
+  pub fn spawn_prefab_at(
+    &mut self,
+    name: &str,
+    transform: Transform,
+  ) -> Result<EntityId, PrefabError> {
+    let now = self.resource::<GameClock>()?.elapsed();
+    let bundle = self.resource::<Prefabs>()?.bundle(name, transform, now)?;
+
+    Ok(self.spawn_bundle(bundle))
+  }
+
+  pub fn insert_bundle<B: Bundle>(&mut self, entity: &EntityId, bundle: B) {
+    if self.is_alive(entity) {
+      bundle.insert(self, entity);
+    }
+  }
+
+  pub fn is_alive(&self, entity: &EntityId) -> bool {
+    self.entities.is_alive(entity)
+  }
+
+  pub fn live_entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+    self.entities.iter()
+  }
+
+  /// Creates the storage for `T`. Components are registered automatically
+  /// the first time one is added, so this is only needed up front.
+  pub fn register<T: Component>(&mut self) -> usize {
+    if let Some(id) = self.component_id::<T>() {
+      return id;
+    }
+
+    let id = self.storages.len();
+    if let Some(existing) = self.component_names.insert(T::name(), id) {
+      panic!(
+        "component name {} is used by both {} and {}",
+        T::name(),
+        self.storages[existing].name(),
+        std::any::type_name::<T>()
+      );
+    }
+    self.component_ids.insert(TypeId::of::<T>(), id);
+    self.storages.push(Box::new(
+      ComponentStorage::<T>::with_kind(T::storage_kind()),
+    ));
+    self.removed.push(vec![]);
+    self.hooks.push(ComponentHooks::new());
+    if let Some(on_despawn) = T::on_despawn() {
+      self.references.push((id, on_despawn));
+    }
+
+    id
   }
 
   pub fn get<T: Component>(&self, entity: &EntityId) -> Option<&T> {
-    self
-      .manager::<T>()
-      .and_then(|manager| manager.get::<T>(entity))
+    self.storage::<T>().and_then(|storage| storage.get(entity))
   }
 
-  pub fn get_mut<T: Component>(&mut self, entity: &EntityId) -> Option<&mut T> {
+  /// Mutably borrows a component, which counts as a change for `Changed<T>`.
+  pub fn get_mut<T: Mutable>(&mut self, entity: &EntityId) -> Option<&mut T> {
+    let tick = self.change_tick();
     self
-      .manager_mut::<T>()
-      .and_then(|manager| manager.get_mut::<T>(entity))
+      .storage_mut::<T>()
+      .and_then(|storage| storage.get_mut(entity, tick))
+  }
+
+  #[allow(dead_code, reason = "systems check presence with query filters")]
+  pub fn has<T: Component>(&self, entity: &EntityId) -> bool {
+    match (self.component_id::<T>(), self.signature(entity)) {
+      (Some(id), Some(signature)) => signature.contains(id),
+      _ => false,
+    }
+  }
+
+  /// Names of every registered component type.
+  #[allow(dead_code, reason = "the game has no inspector yet")]
+  pub fn component_names(&self) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = self.component_names.keys().copied().collect();
+    names.sort_unstable();
+    names
+  }
+
+  /// Every component `entity` has, with its registered name.
+  pub fn components_of(&self, entity: &EntityId) -> Vec<(&'static str, &dyn Component)> {
+    self.signature(entity).map_or(vec![], |signature| {
+      signature
+        .iter()
+        .filter_map(|id| {
+          let storage = &self.storages[id];
+          storage.get_dyn(entity).map(|c| (storage.name(), c))
+        })
+        .collect()
+    })
+  }
+
+  #[allow(dead_code, reason = "the game has no inspector yet")]
+  pub fn reflect(&self, entity: &EntityId, name: &str) -> Option<&dyn Reflect> {
+    let id = *self.component_names.get(name)?;
+
+    self.storages[id].get_dyn(entity)?.as_reflect()
+  }
+
+  /// Reflected mutable access, which counts as a change for `Changed<T>`.
+  /// `None` for immutable components like `Tag`.
+  #[allow(dead_code, reason = "the game has no inspector yet")]
+  pub fn reflect_mut(&mut self, entity: &EntityId, name: &str) -> Option<&mut dyn Reflect> {
+    let id = *self.component_names.get(name)?;
+    let tick = self.change_tick();
+
+    self.storages[id]
+      .get_dyn_mut(entity, tick)?
+      .as_reflect_mut()
+  }
+
+  pub fn signature(&self, entity: &EntityId) -> Option<&Signature> {
+    if !self.is_alive(entity) {
+      return None;
+    }
+
+    self.signatures.get(entity.index() as usize)
   }
 
   pub fn add<T: Component>(&mut self, entity: &EntityId, component: T) {
-    self
-      .manager_mut::<T>()
-      .map(|manager| manager.add(entity, component));
+    if !self.is_alive(entity) {
+      return;
+    }
+
+    let tick = self.change_tick();
+    let id = self.register::<T>();
+    let replacing = self.storages[id].contains(entity);
+    if replacing {
+      self.run_hooks(Lifecycle::Replace, id, entity);
+    }
+
+    let tagging = TypeId::of::<T>() == TypeId::of::<Tag>();
+    if tagging {
+      self.unindex_tags(entity);
+    }
+
+    self.signatures[entity.index() as usize].insert(id);
+    if let Some(storage) = self.any_storage_mut::<T>() {
+      storage.insert(entity, component, tick);
+    }
+
+    if tagging {
+      self.index_tags(entity);
+    }
+    self.index_references(id, entity);
+
+    if !replacing {
+      self.run_hooks(Lifecycle::Add, id, entity);
+    }
   }
 
+  /// Despawns `entity` along with everything attached to it.
   pub fn remove(&mut self, entity: &EntityId) {
-    // TODO keep track of which ones actually have an entity
-    for manager in self.component_managers.values_mut() {
-      manager.remove(entity);
+    if !self.is_alive(entity) {
+      return;
+    }
+
+    self.remove_parent(entity);
+    for child in self.children(entity) {
+      self.remove(&child);
+    }
+
+    self.entities.free(entity);
+    self.unindex_tags(entity);
+
+    let tick = self.change_tick();
+    let signature = mem::take(&mut self.signatures[entity.index() as usize]);
+    for id in signature.iter() {
+      self.run_hooks(Lifecycle::Remove, id, entity);
+      self.storages[id].remove(entity);
+      self.removed[id].push((*entity, tick));
+      self.reference_index.remove(entity, id);
+    }
+
+    self.release_references(entity);
+  }
+
+  /// Runs `hook` with the new component whenever a `T` is attached to an
+  /// entity that didn't have one.
+  #[allow(dead_code, reason = "no game system registers hooks yet")]
+  pub fn on_add<T: Component, F>(&mut self, hook: F)
+  where
+    F: Fn(EntityId, &T, &mut Commands) + Send + Sync + 'static,
+  {
+    let id = self.register::<T>();
+    self.hooks[id].push(Lifecycle::Add, hook);
+  }
+
+  /// Runs `hook` with the old component whenever a `T` is overwritten.
+  #[allow(dead_code, reason = "no game system registers hooks yet")]
+  pub fn on_replace<T: Component, F>(&mut self, hook: F)
+  where
+    F: Fn(EntityId, &T, &mut Commands) + Send + Sync + 'static,
+  {
+    let id = self.register::<T>();
+    self.hooks[id].push(Lifecycle::Replace, hook);
+  }
+
+  /// Runs `hook` whenever a `T` is removed, including when its entity is
+  /// despawned.
+  #[allow(dead_code, reason = "no game system registers hooks yet")]
+  pub fn on_remove<T: Component, F>(&mut self, hook: F)
+  where
+    F: Fn(EntityId, &T, &mut Commands) + Send + Sync + 'static,
+  {
+    let id = self.register::<T>();
+    self.hooks[id].push(Lifecycle::Remove, hook);
+  }
+
+  /// Queues the commands of `id`'s hooks for `lifecycle`. They run the next
+  /// time commands are applied.
+  fn run_hooks(&mut self, lifecycle: Lifecycle, id: usize, entity: &EntityId) {
+    let World {
+      hooks,
+      storages,
+      hook_commands,
+      ..
+    } = self;
+
+    if hooks[id].is_empty(lifecycle) {
+      return;
+    }
+    if let Some(component) = storages[id].get_dyn(entity) {
+      hooks[id].run(lifecycle, *entity, component, hook_commands);
+    }
+  }
+
+  /// Applies each referencing component's `OnDespawn` now that `target` is
+  /// gone.
+  fn release_references(&mut self, target: &EntityId) {
+    for (entity, id) in self.reference_index.take(target) {
+      let on_despawn = match self.on_despawn(id) {
+        Some(on_despawn) => on_despawn,
+        None => continue,
+      };
+      match on_despawn {
+        OnDespawn::Remove => self.remove_component_id(id, &entity),
+        OnDespawn::Clear => {
+          let tick = self.change_tick();
+          let cleared = self.storages[id]
+            .get_dyn_mut(&entity, tick)
+            .is_some_and(|component| component.clear_entity_ref(target));
+          if cleared {
+            self.index_references(id, &entity);
+          } else {
+            self.remove_component_id(id, &entity);
+          }
+        }
+        OnDespawn::Report => {
+          let dangling = DanglingRef {
+            component: self.storages[id].name(),
+            entity,
+            target: *target,
+          };
+          self.send(dangling);
+        }
+      }
     }
   }
 
+  fn on_despawn(&self, id: usize) -> Option<OnDespawn> {
+    self
+      .references
+      .iter()
+      .find(|(reference, _)| *reference == id)
+      .map(|(_, on_despawn)| *on_despawn)
+  }
+
+  /// Components with `#[entity]` fields are immutable outside the world, so
+  /// re-indexing wherever the world changes them keeps the index exact.
+  fn index_references(&mut self, id: usize, entity: &EntityId) {
+    if self.on_despawn(id).is_none() {
+      return;
+    }
+
+    match self.storages[id].get_dyn(entity) {
+      Some(component) => {
+        let targets = component.entity_refs();
+        self.reference_index.insert(*entity, id, targets);
+      }
+      None => self.reference_index.remove(entity, id),
+    }
+  }
+
+  /// Every reference from a component to a despawned entity. Only components
+  /// that report dangling references, or were given an already despawned
+  /// entity, should ever show up here.
+  pub fn dangling_references(&self) -> Vec<DanglingRef> {
+    let mut dangling = vec![];
+
+    for (id, _) in &self.references {
+      let storage = &self.storages[*id];
+      for entity in storage.entities() {
+        let component = storage.get_dyn(entity).unwrap();
+        for target in component.entity_refs() {
+          if !self.is_alive(&target) {
+            dangling.push(DanglingRef {
+              component: storage.name(),
+              entity: *entity,
+              target,
+            });
+          }
+        }
+      }
+    }
+
+    dangling
+  }
+
+  /// Checks for dangling references each time commands are applied, and
+  /// panics on any it finds. On by default in tests.
+  #[cfg(test)]
+  pub fn set_strict_references(&mut self, strict: bool) {
+    self.strict_references = strict;
+  }
+
   pub fn remove_all(&mut self, entities: Vec<EntityId>) {
     for entity in entities {
       self.remove(&entity);
@@ -75,43 +446,366 @@ impl World {
   }
 
   pub fn remove_component<T: Component>(&mut self, entity: &EntityId) {
+    if !self.is_alive(entity) {
+      return;
+    }
+
+    if let Some(id) = self.component_id::<T>() {
+      self.remove_component_id(id, entity);
+    }
+  }
+
+  fn remove_component_id(&mut self, id: usize, entity: &EntityId) {
+    let tick = self.change_tick();
+    if self.storages[id].contains(entity) {
+      self.run_hooks(Lifecycle::Remove, id, entity);
+      self.removed[id].push((*entity, tick));
+    }
+    if Some(id) == self.component_id::<Tag>() {
+      self.unindex_tags(entity);
+    }
+    self.signatures[entity.index() as usize].remove(id);
+    self.storages[id].remove(entity);
+    self.reference_index.remove(entity, id);
+  }
+
+  /// Adds `tag_type` to the entity's `Tag`, adding a `Tag` if it has none.
+  #[allow(dead_code, reason = "no system looks up or changes tags by type yet")]
+  pub fn add_tag(&mut self, entity: &EntityId, tag_type: TagType) {
+    match self.tag_mut(entity) {
+      Some(tag) => tag.insert(tag_type.clone()),
+      None => return self.add(entity, Tag::new(tag_type)),
+    }
+
+    self.tag_index.insert(*entity, tag_type);
+  }
+
+  #[allow(dead_code, reason = "no system looks up or changes tags by type yet")]
+  pub fn remove_tag(&mut self, entity: &EntityId, tag_type: &TagType) {
+    if let Some(tag) = self.tag_mut(entity) {
+      tag.remove(tag_type);
+      self.tag_index.remove(entity, tag_type);
+    }
+  }
+
+  /// Entities tagged `tag_type`, in id order.
+  #[allow(dead_code, reason = "no system looks up or changes tags by type yet")]
+  pub fn tagged(&self, tag_type: TagType) -> Vec<EntityId> {
+    self.tag_index.get(&tag_type)
+  }
+
+  /// Shapes whose level is in `levels`, e.g. `world.tagged_shapes(2..)`.
+  #[allow(dead_code, reason = "no system looks up or changes tags by type yet")]
+  pub fn tagged_shapes<R: RangeBounds<u8>>(&self, levels: R) -> Vec<EntityId> {
+    self.tag_index.shapes(levels)
+  }
+
+  /// `Tag` is immutable outside the world, so the index can't go stale.
+  fn tag_mut(&mut self, entity: &EntityId) -> Option<&mut Tag> {
+    let tick = self.change_tick();
     self
-      .manager_mut::<T>()
-      .map(|manager| manager.remove(entity));
+      .any_storage_mut::<Tag>()
+      .and_then(|storage| storage.get_mut(entity, tick))
   }
 
-  pub fn components<T: Component>(&self) -> Vec<&T> {
-    if let Some(manager) = self.manager::<T>() {
-      return manager.components::<T>();
+  fn index_tags(&mut self, entity: &EntityId) {
+    for tag_type in self.tags_of(entity) {
+      self.tag_index.insert(*entity, tag_type);
     }
+  }
 
-    vec![]
+  fn unindex_tags(&mut self, entity: &EntityId) {
+    for tag_type in self.tags_of(entity) {
+      self.tag_index.remove(entity, &tag_type);
+    }
   }
 
-  pub fn entities<T: Component>(&self) -> Vec<EntityId> {
-    if let Some(manager) = self.manager::<T>() {
-      return manager.entities().into_iter().collect();
+  fn tags_of(&self, entity: &EntityId) -> Vec<TagType> {
+    self
+      .get::<Tag>(entity)
+      .map_or(vec![], |tag| tag.tags().to_vec())
+  }
+
+  /// Attaches `child` to `parent`, detaching it from any previous parent.
+  /// Attaching an entity to itself or one of its descendants is ignored.
+  #[allow(dead_code, reason = "nothing is attached to a parent yet")]
+  pub fn set_parent(&mut self, child: &EntityId, parent: &EntityId) {
+    if !self.is_alive(child) || !self.is_alive(parent) || self.is_ancestor(child, parent) {
+      return;
     }
 
-    vec![]
+    self.remove_parent(child);
+    self.add(child, Parent::new(*parent));
+
+    match self.children_mut(parent) {
+      Some(children) => {
+        children.entities.push(*child);
+        self.index_children(parent);
+      }
+      None => self.add(parent, Children::new(vec![*child])),
+    }
   }
 
+  pub fn remove_parent(&mut self, child: &EntityId) {
+    let parent = match self.get::<Parent>(child) {
+      Some(parent) => parent.entity,
+      None => return,
+    };
+
+    self.remove_component::<Parent>(child);
+    if let Some(children) = self.children_mut(&parent) {
+      children.entities.retain(|c| c != child);
+      self.index_children(&parent);
+    }
+  }
+
+  #[allow(dead_code, reason = "nothing is attached to a parent yet")]
+  pub fn parent(&self, entity: &EntityId) -> Option<EntityId> {
+    self.get::<Parent>(entity).map(|parent| parent.entity)
+  }
+
+  pub fn children(&self, entity: &EntityId) -> Vec<EntityId> {
+    self
+      .get::<Children>(entity)
+      .map_or(vec![], |children| children.entities.clone())
+  }
+
+  /// `Children` is immutable outside the world. Call `index_children` after
+  /// changing it.
+  fn children_mut(&mut self, entity: &EntityId) -> Option<&mut Children> {
+    let tick = self.change_tick();
+    self
+      .any_storage_mut::<Children>()
+      .and_then(|storage| storage.get_mut(entity, tick))
+  }
+
+  fn index_children(&mut self, entity: &EntityId) {
+    if let Some(id) = self.component_id::<Children>() {
+      self.index_references(id, entity);
+    }
+  }
+
+  fn is_ancestor(&self, ancestor: &EntityId, entity: &EntityId) -> bool {
+    let mut current = Some(*entity);
+
+    while let Some(entity) = current {
+      if entity == *ancestor {
+        return true;
+      }
+      current = self.parent(&entity);
+    }
+
+    false
+  }
+
+  pub fn apply(&mut self, commands: &mut Commands) {
+    for command in commands.drain() {
+      command(self);
+    }
+
+    // commands queued by hooks can trigger more hooks
+    while !self.hook_commands.is_empty() {
+      let mut queued = mem::take(&mut self.hook_commands);
+      for command in queued.drain() {
+        command(self);
+      }
+    }
+
+    if self.strict_references {
+      if let Some(dangling) = self.dangling_references().first() {
+        panic!("{}", dangling);
+      }
+    }
+  }
+
+  #[allow(dead_code, reason = "systems iterate with queries instead")]
+  pub fn components<T: Component>(&self) -> &[T] {
+    self
+      .storage::<T>()
+      .map_or(&[], |storage| storage.components())
+  }
+
+  #[allow(dead_code, reason = "systems iterate with queries instead")]
+  pub fn entities<T: Component>(&self) -> Vec<EntityId> {
+    self
+      .storage::<T>()
+      .map_or(vec![], |storage| storage.entities().to_vec())
+  }
+
+  #[allow(dead_code, reason = "systems iterate with queries instead")]
   pub fn entities_with(&self, components: Vec<TypeId>) -> Vec<EntityId> {
-    let managers = components
+    let ids: Option<Vec<usize>> = components
+      .iter()
+      .map(|c| self.component_ids.get(c).copied())
+      .collect();
+
+    let ids = match ids {
+      Some(ids) => ids,
+      None => return vec![],
+    };
+
+    let mut required = Signature::new();
+    for id in &ids {
+      required.insert(*id);
+    }
+
+    // only the entities of the smallest storage can match
+    let smallest = ids
       .iter()
-      .filter_map(|c| self.component_managers.get(c));
+      .map(|id| &self.storages[*id])
+      .min_by_key(|s| s.len());
 
-    let entities = managers.map(|m| m.entities()).collect();
+    smallest.map_or(vec![], |storage| {
+      storage
+        .entities()
+        .iter()
+        .filter(|e| self.signatures[e.index() as usize].contains_all(&required))
+        .copied()
+        .collect()
+    })
+  }
 
-    intersections(entities)
+  pub fn send<E: Event>(&mut self, event: E) {
+    self
+      .events
+      .entry(TypeId::of::<E>())
+      .or_insert_with(|| Box::new(Events::<E>::new()))
+      .as_any_mut()
+      .downcast_mut::<Events<E>>()
+      .unwrap()
+      .send(event);
   }
 
-  fn manager<T: Component>(&self) -> Option<&ComponentManager> {
-    self.component_managers.get(&TypeId::of::<T>())
+  pub fn read<E: Event>(&self, reader: ReaderId) -> impl Iterator<Item = &E> {
+    self
+      .events
+      .get(&TypeId::of::<E>())
+      .and_then(|events| events.as_any().downcast_ref::<Events<E>>())
+      .into_iter()
+      .flat_map(move |events| events.read(reader))
   }
 
-  fn manager_mut<T: Component>(&mut self) -> Option<&mut ComponentManager> {
-    self.component_managers.get_mut(&TypeId::of::<T>())
+  /// Ages every event channel and removal log by one frame, dropping entries
+  /// that every system has had a chance to read.
+  pub fn update_events(&mut self) {
+    for events in self.events.values_mut() {
+      events.update();
+    }
+
+    let frame_tick = self.frame_tick;
+    for removed in &mut self.removed {
+      removed.retain(|(_, tick)| *tick >= frame_tick);
+    }
+    self.frame_tick = self.change_tick.fetch_add(1, Ordering::SeqCst) + 1;
+  }
+
+  pub fn change_tick(&self) -> u32 {
+    self.change_tick.load(Ordering::SeqCst)
+  }
+
+  /// Entities that lost their `T`, or were despawned with one, since `reader`
+  /// last asked.
+  #[allow(dead_code, reason = "no system reacts to removed components yet")]
+  pub fn removed<T: Component>(&self, reader: ReaderId) -> Vec<EntityId> {
+    let id = match self.component_id::<T>() {
+      Some(id) => id,
+      None => return vec![],
+    };
+    let ticks = self.advance_cursor(reader, TypeId::of::<Removed<T>>());
+
+    self.removed[id]
+      .iter()
+      .filter(|(_, tick)| *tick > ticks.last_run)
+      .map(|(entity, _)| *entity)
+      .collect()
+  }
+
+  pub fn insert_resource<R: Resource>(&mut self, resource: R) {
+    self.resources.insert(TypeId::of::<R>(), Box::new(resource));
+  }
+
+  pub fn resource<R: Resource>(&self) -> Result<&R, ResourceError> {
+    self
+      .resources
+      .get(&TypeId::of::<R>())
+      .and_then(|resource| resource.downcast_ref::<R>())
+      .ok_or_else(ResourceError::missing::<R>)
+  }
+
+  pub fn resource_mut<R: Resource>(&mut self) -> Result<&mut R, ResourceError> {
+    self
+      .resources
+      .get_mut(&TypeId::of::<R>())
+      .and_then(|resource| resource.downcast_mut::<R>())
+      .ok_or_else(ResourceError::missing::<R>)
+  }
+
+  pub fn query<Q: Query>(&mut self) -> QueryIter<'_, Q> {
+    let ticks = Ticks {
+      last_run: 0,
+      current: self.change_tick(),
+    };
+
+    QueryIter::new(self, ticks)
+  }
+
+  /// Fetches `Q` for one entity, or `None` if it doesn't match.
+  pub fn query_one<Q: Query>(&mut self, entity: &EntityId) -> Option<Q::Item<'_>> {
+    if !self.is_alive(entity) {
+      return None;
+    }
+
+    let ticks = Ticks {
+      last_run: 0,
+      current: self.change_tick(),
+    };
+
+    query::fetch_one::<Q>(self, ticks, entity)
+  }
+
+  /// Queries with `Added<T>` and `Changed<T>` filters relative to the last
+  /// time `reader` ran this same query. Changes the query makes itself are
+  /// not reported back to it.
+  #[allow(dead_code, reason = "no system reacts to changes yet")]
+  pub fn query_since<Q: Query + 'static>(&mut self, reader: ReaderId) -> QueryIter<'_, Q> {
+    let ticks = self.advance_cursor(reader, TypeId::of::<Q>());
+
+    QueryIter::new(self, ticks)
+  }
+
+  pub fn storage<T: Component>(&self) -> Option<&ComponentStorage<T>> {
+    self.component_id::<T>().and_then(|id| {
+      self.storages[id]
+        .as_any()
+        .downcast_ref::<ComponentStorage<T>>()
+    })
+  }
+
+  /// Only for mutable components, so `Tag` and components with `#[entity]`
+  /// fields can't change behind the world's indexes.
+  pub(crate) fn storage_mut<T: Mutable>(&mut self) -> Option<&mut ComponentStorage<T>> {
+    self.any_storage_mut::<T>()
+  }
+
+  fn any_storage_mut<T: Component>(&mut self) -> Option<&mut ComponentStorage<T>> {
+    match self.component_id::<T>() {
+      Some(id) => self.storages[id]
+        .as_any_mut()
+        .downcast_mut::<ComponentStorage<T>>(),
+      None => None,
+    }
+  }
+
+  fn component_id<T: Component>(&self) -> Option<usize> {
+    self.component_ids.get(&TypeId::of::<T>()).copied()
+  }
+
+  fn advance_cursor(&self, reader: ReaderId, key: TypeId) -> Ticks {
+    let mut cursors = self.change_cursors.lock().unwrap();
+    let current = self.change_tick.fetch_add(1, Ordering::SeqCst);
+    let last_run = cursors.insert((reader, key), current).unwrap_or(0);
+
+    Ticks { last_run, current }
   }
 }
 
@@ -135,8 +829,8 @@ mod tests {
 
       let transform1 = Transform::new(0., 0.);
       let transform2 = Transform::new(1., 1.);
-      let expiration1 = Expirable::new(Duration::from_secs(1));
-      let expiration2 = Expirable::new(Duration::from_secs(2));
+      let expiration1 = Expirable::new(Duration::default(), Duration::from_secs(1));
+      let expiration2 = Expirable::new(Duration::default(), Duration::from_secs(2));
 
       world.add(&entity1, transform1);
       world.add(&entity2, transform2);
@@ -172,12 +866,12 @@ mod tests {
       let entity2 = world.create_entity();
 
       let transform1 = Transform::new(0., 0.);
-      let expiration1 = Expirable::new(Duration::from_secs(1));
-      let expiration2 = Expirable::new(Duration::from_secs(2));
+      let expiration1 = Expirable::new(Duration::default(), Duration::from_secs(1));
+      let expiration2 = Expirable::new(Duration::default(), Duration::from_secs(2));
 
-      let mut expected_transforms = vec![&entity1];
+      let mut expected_transforms = [&entity1];
       expected_transforms.sort();
-      let mut expected_expirations = vec![&entity1, &entity2];
+      let mut expected_expirations = [&entity1, &entity2];
       expected_expirations.sort();
 
       world.add(&entity1, transform1);
@@ -198,8 +892,8 @@ mod tests {
       let entity2 = world.create_entity();
 
       let transform1 = Transform::new(0., 0.);
-      let expiration1 = Expirable::new(Duration::from_secs(1));
-      let expiration2 = Expirable::new(Duration::from_secs(2));
+      let expiration1 = Expirable::new(Duration::default(), Duration::from_secs(1));
+      let expiration2 = Expirable::new(Duration::default(), Duration::from_secs(2));
       let expected_points = vec![Point2::new(0.0f32, 0.0f32)];
       let expected_durations = vec![Duration::from_secs(1), Duration::from_secs(2)];
 
@@ -227,8 +921,8 @@ mod tests {
       let entity2 = world.create_entity();
 
       let transform1 = Transform::new(0., 0.);
-      let expiration1 = Expirable::new(Duration::from_secs(1));
-      let expiration2 = Expirable::new(Duration::from_secs(2));
+      let expiration1 = Expirable::new(Duration::default(), Duration::from_secs(1));
+      let expiration2 = Expirable::new(Duration::default(), Duration::from_secs(2));
 
       world.add(&entity1, transform1);
       world.add(&entity1, expiration1);
@@ -240,6 +934,422 @@ mod tests {
       assert_eq!(entities.len(), 1);
       assert_eq!(entities[0], entity1);
     }
+
+    #[test]
+    fn detects_stale_entities() {
+      let mut world = World::new();
+
+      let entity1 = world.create_entity();
+      world.add(&entity1, Transform::new(0., 0.));
+      world.remove(&entity1);
+
+      let entity2 = world.create_entity();
+      world.add(&entity2, Transform::new(1., 1.));
+
+      assert!(!world.is_alive(&entity1));
+      assert!(world.is_alive(&entity2));
+      assert_eq!(entity1.index(), entity2.index());
+      assert!(world.get::<Transform>(&entity1).is_none());
+
+      world.add(
+        &entity1,
+        Expirable::new(Duration::default(), Duration::from_secs(1)),
+      );
+      assert!(world.get::<Expirable>(&entity2).is_none());
+    }
+
+    #[test]
+    fn enumerates_live_entities() {
+      let mut world = World::new();
+
+      let entity1 = world.create_entity();
+      let entity2 = world.create_entity();
+      world.remove(&entity1);
+
+      let entities: Vec<EntityId> = world.live_entities().collect();
+
+      assert_eq!(entities, vec![entity2]);
+    }
+
+    #[test]
+    fn tracks_which_components_an_entity_has() {
+      let mut world = World::new();
+
+      let entity = world.create_entity();
+      world.add(&entity, Transform::new(0., 0.));
+      world.add(
+        &entity,
+        Expirable::new(Duration::default(), Duration::from_secs(1)),
+      );
+
+      assert!(world.has::<Transform>(&entity));
+      assert!(world.has::<Expirable>(&entity));
+
+      world.remove_component::<Transform>(&entity);
+
+      assert!(!world.has::<Transform>(&entity));
+      assert!(world.get::<Transform>(&entity).is_none());
+      assert!(world.has::<Expirable>(&entity));
+
+      world.remove(&entity);
+
+      assert!(!world.has::<Expirable>(&entity));
+      assert!(world.components::<Expirable>().is_empty());
+    }
+
+    #[test]
+    fn registers_components_on_first_use() {
+      #[derive(Component)]
+      struct Unregistered;
+
+      let mut world = World::new();
+
+      let entity = world.create_entity();
+      world.add(&entity, Transform::new(0., 0.));
+
+      let types = vec![TypeId::of::<Transform>(), TypeId::of::<Unregistered>()];
+      assert!(world.entities_with(types.clone()).is_empty());
+      assert!(world.get::<Unregistered>(&entity).is_none());
+
+      world.add(&entity, Unregistered);
+
+      assert_eq!(world.entities_with(types), vec![entity]);
+      assert!(world.has::<Unregistered>(&entity));
+    }
+
+    #[test]
+    fn can_send_and_read_events() {
+      use crate::events::{DamagedEvent, ReaderId};
+
+      struct Reader;
+
+      let mut world = World::new();
+      let entity = world.create_entity();
+
+      assert_eq!(
+        world.read::<DamagedEvent>(ReaderId::of::<Reader>()).count(),
+        0
+      );
+
+      world.send(DamagedEvent::new(entity));
+      world.update_events();
+
+      let damaged: Vec<EntityId> = world
+        .read::<DamagedEvent>(ReaderId::of::<Reader>())
+        .map(|e| e.entity)
+        .collect();
+      assert_eq!(damaged, vec![entity]);
+
+      world.update_events();
+      world.send(DamagedEvent::new(entity));
+      world.update_events();
+      world.update_events();
+
+      assert_eq!(
+        world.read::<DamagedEvent>(ReaderId::of::<Reader>()).count(),
+        0
+      );
+    }
+
+    #[test]
+    fn can_insert_and_access_resources() {
+      use crate::resources::{ArenaSize, Scoreboard};
+
+      let mut world = World::new();
+
+      world.insert_resource(ArenaSize::new(800., 600.));
+      world.resource_mut::<ArenaSize>().unwrap().width = 1024.;
+
+      assert_eq!(
+        world.resource::<ArenaSize>(),
+        Ok(&ArenaSize::new(1024., 600.))
+      );
+
+      let error = world.resource::<Scoreboard>().unwrap_err();
+      assert!(error.to_string().contains("Scoreboard"));
+    }
+
+    #[test]
+    fn removes_children_with_their_parent() {
+      let mut world = World::new();
+
+      let ship = world.create_entity();
+      let turret = world.create_entity();
+      let barrel = world.create_entity();
+      let shield = world.create_entity();
+
+      world.set_parent(&turret, &ship);
+      world.set_parent(&barrel, &turret);
+      world.set_parent(&shield, &ship);
+      world.set_parent(&ship, &barrel);
+
+      assert_eq!(world.children(&ship), vec![turret, shield]);
+      assert_eq!(world.parent(&barrel), Some(turret));
+      assert_eq!(world.parent(&ship), None);
+
+      world.remove_parent(&shield);
+      world.remove(&ship);
+
+      assert!(!world.is_alive(&turret));
+      assert!(!world.is_alive(&barrel));
+      assert!(world.is_alive(&shield));
+      assert!(!world.has::<Parent>(&shield));
+    }
+
+    #[test]
+    fn enumerates_and_edits_components_by_name() {
+      use crate::reflect::Value;
+
+      let mut world = World::new();
+
+      let entity = world.create_entity();
+      world.add(&entity, Transform::new(1., 2.));
+      world.add(
+        &entity,
+        Expirable::new(Duration::default(), Duration::from_secs(1)),
+      );
+
+      let names: Vec<&str> = world
+        .components_of(&entity)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+      assert_eq!(names, vec!["Transform", "Expirable"]);
+      assert_eq!(world.component_names(), vec!["Expirable", "Transform"]);
+
+      world
+        .reflect_mut(&entity, "Transform")
+        .unwrap()
+        .set_field("rotation", &Value::Float(45.))
+        .unwrap();
+
+      assert_eq!(world.get::<Transform>(&entity).unwrap().rotation, 45.);
+      assert_eq!(
+        world
+          .reflect(&entity, "Expirable")
+          .unwrap()
+          .field("expiration"),
+        Some(Value::Float(1.))
+      );
+      assert!(world.reflect(&entity, "Drawable").is_none());
+    }
+
+    #[test]
+    fn reports_removed_components_once_per_reader() {
+      use crate::events::ReaderId;
+
+      struct Reader;
+
+      let mut world = World::new();
+
+      let entity1 = world.create_entity();
+      world.add(&entity1, Transform::new(0., 0.));
+      let entity2 = world.create_entity();
+      world.add(&entity2, Transform::new(1., 1.));
+      world.add(
+        &entity2,
+        Expirable::new(Duration::default(), Duration::from_secs(1)),
+      );
+
+      world.remove_component::<Transform>(&entity1);
+      world.remove_component::<Transform>(&entity1);
+      world.remove(&entity2);
+
+      let reader = ReaderId::of::<Reader>();
+      assert_eq!(world.removed::<Transform>(reader), vec![entity1, entity2]);
+      assert_eq!(world.removed::<Expirable>(reader), vec![entity2]);
+      assert!(world.removed::<Transform>(reader).is_empty());
+
+      let entity3 = world.create_entity();
+      world.add(&entity3, Transform::new(2., 2.));
+      world.remove(&entity3);
+      world.update_events();
+      world.update_events();
+
+      assert!(world.removed::<Transform>(reader).is_empty());
+    }
+
+    #[test]
+    fn indexes_entities_by_tag() {
+      use crate::components::{Tag, TagType};
+
+      let mut world = World::new();
+
+      let ship = world.create_entity();
+      world.add(&ship, Tag::new(TagType::Ship));
+      let square = world.create_entity();
+      world.add(&square, Tag::new(TagType::Shape(1)));
+      let hexagon = world.create_entity();
+      world.add(&hexagon, Tag::new(TagType::Shape(2)));
+      let octagon = world.create_entity();
+      world.add(&octagon, Tag::new(TagType::Shape(3)).with(TagType::Ship));
+
+      assert_eq!(world.tagged(TagType::Ship), vec![ship, octagon]);
+      assert_eq!(world.tagged_shapes(2..), vec![hexagon, octagon]);
+      assert_eq!(world.tagged_shapes(..=2), vec![square, hexagon]);
+
+      world.add_tag(&square, TagType::Ship);
+      world.remove_tag(&octagon, &TagType::Ship);
+      world.add(&hexagon, Tag::new(TagType::Bullet));
+      world.remove(&ship);
+
+      assert_eq!(world.tagged(TagType::Ship), vec![square]);
+      assert_eq!(world.tagged(TagType::Bullet), vec![hexagon]);
+      assert_eq!(world.tagged_shapes(1..=3), vec![square, octagon]);
+
+      world.remove_component::<Tag>(&square);
+      assert!(world.tagged(TagType::Ship).is_empty());
+    }
+
+    #[test]
+    fn only_changes_tags_through_the_index() {
+      use crate::components::{Tag, TagType};
+
+      let mut world = World::new();
+      let entity = world.create_entity();
+      world.add(&entity, Tag::new(TagType::Ship));
+
+      assert!(world.reflect(&entity, "Tag").is_some());
+      assert!(world.reflect_mut(&entity, "Tag").is_none());
+
+      world.add_tag(&entity, TagType::Bullet);
+      assert_eq!(world.tagged(TagType::Bullet), vec![entity]);
+      assert!(world.get::<Tag>(&entity).unwrap().has(&TagType::Bullet));
+
+      world.remove_tag(&entity, &TagType::Ship);
+      assert!(world.tagged(TagType::Ship).is_empty());
+      assert_eq!(
+        world.get::<Tag>(&entity).unwrap().tags(),
+        &[TagType::Bullet]
+      );
+    }
+
+    #[derive(Component)]
+    struct Target {
+      #[entity]
+      entity: EntityId,
+    }
+
+    #[derive(Component)]
+    #[component(on_despawn = "clear")]
+    struct Tracking {
+      #[entity]
+      entity: Option<EntityId>,
+      #[entity]
+      others: Vec<EntityId>,
+    }
+
+    #[derive(Component)]
+    #[component(on_despawn = "report")]
+    struct Marker {
+      #[entity]
+      entity: EntityId,
+    }
+
+    #[test]
+    fn releases_references_to_despawned_entities() {
+      let mut world = World::new();
+
+      let target = world.create_entity();
+      let other = world.create_entity();
+      let hunter = world.create_entity();
+      world.add(&hunter, Target { entity: target });
+      world.add(
+        &hunter,
+        Tracking {
+          entity: Some(target),
+          others: vec![target, other],
+        },
+      );
+
+      world.remove(&target);
+
+      assert!(!world.has::<Target>(&hunter));
+      let tracking = world.get::<Tracking>(&hunter).unwrap();
+      assert_eq!(tracking.entity, None);
+      assert_eq!(tracking.others, vec![other]);
+      assert!(world.dangling_references().is_empty());
+    }
+
+    #[test]
+    fn only_releases_current_references() {
+      let mut world = World::new();
+
+      let first = world.create_entity();
+      let second = world.create_entity();
+      let hunter = world.create_entity();
+      let retired = world.create_entity();
+      world.add(&hunter, Target { entity: first });
+      world.add(&hunter, Target { entity: second });
+      world.add(&retired, Target { entity: second });
+      world.remove_component::<Target>(&retired);
+      world.add(
+        &retired,
+        Tracking {
+          entity: Some(hunter),
+          others: vec![],
+        },
+      );
+
+      world.remove(&first);
+      assert!(world.has::<Target>(&hunter));
+
+      world.remove(&second);
+      assert!(!world.has::<Target>(&hunter));
+      assert_eq!(
+        world.get::<Tracking>(&retired).unwrap().entity,
+        Some(hunter)
+      );
+
+      let old_parent = world.create_entity();
+      let new_parent = world.create_entity();
+      let child = world.create_entity();
+      world.set_parent(&child, &old_parent);
+      world.set_parent(&child, &new_parent);
+
+      world.remove(&child);
+      assert!(world.children(&old_parent).is_empty());
+      assert!(world.children(&new_parent).is_empty());
+      assert!(world.dangling_references().is_empty());
+    }
+
+    #[test]
+    fn reports_dangling_references() {
+      let mut world = World::new();
+      world.set_strict_references(false);
+
+      let target = world.create_entity();
+      let marked = world.create_entity();
+      world.add(&marked, Marker { entity: target });
+      world.remove(&target);
+
+      let dangling = DanglingRef {
+        component: "Marker",
+        entity: marked,
+        target,
+      };
+      assert_eq!(world.dangling_references(), vec![dangling]);
+      assert_eq!(
+        world
+          .read::<DanglingRef>(ReaderId::of::<DanglingRef>())
+          .collect::<Vec<_>>(),
+        vec![&dangling]
+      );
+    }
+
+    #[test]
+    #[should_panic(expected = "references despawned entity")]
+    fn panics_on_dangling_references_in_strict_mode() {
+      let mut world = World::new();
+
+      let target = world.create_entity();
+      world.remove(&target);
+
+      let mut commands = Commands::new();
+      let hunter = world.create_entity();
+      commands.insert(hunter, Target { entity: target });
+      world.apply(&mut commands);
+    }
   }
 
   fn assert_contains_all<T: std::cmp::PartialEq>(v1: Vec<T>, v2: Vec<T>) {